@@ -1,5 +1,6 @@
+use crate::time::Clock;
 use crate::types::*;
-use godot::classes::{Os, Time};
+use godot::classes::Os;
 use godot::prelude::*;
 
 #[godot_api]
@@ -31,6 +32,9 @@ impl IResource for MCAPWriteOptions {
             calculate_data_section_crc: true,
             calculate_summary_section_crc: true,
             calculate_attachment_crcs: true,
+            time_unit: MCAPTimeUnit::Microseconds,
+            clock_source: MCAPClockSource::EngineTicks,
+            custom_clock: Callable::invalid(),
             #[cfg(any(feature = "zstd", feature = "lz4"))]
             compression_level: 0,
             #[cfg(feature = "zstd")]
@@ -52,6 +56,27 @@ impl MCAPWriteOptions {
     #[constant]
     /// LZ4 frame compression.
     const MCAP_COMPRESSION_LZ4: i64 = MCAPCompression::Lz4 as i64;
+    #[constant]
+    /// Timestamps stored in microseconds.
+    const MCAP_TIME_UNIT_MICROSECONDS: i64 = MCAPTimeUnit::Microseconds as i64;
+    #[constant]
+    /// Timestamps stored in nanoseconds.
+    const MCAP_TIME_UNIT_NANOSECONDS: i64 = MCAPTimeUnit::Nanoseconds as i64;
+    #[constant]
+    /// Timestamps stored in milliseconds.
+    const MCAP_TIME_UNIT_MILLISECONDS: i64 = MCAPTimeUnit::Milliseconds as i64;
+    #[constant]
+    /// Stamp with engine ticks.
+    const MCAP_CLOCK_ENGINE_TICKS: i64 = MCAPClockSource::EngineTicks as i64;
+    #[constant]
+    /// Stamp with the Unix wall clock.
+    const MCAP_CLOCK_UNIX_WALL: i64 = MCAPClockSource::UnixWall as i64;
+    #[constant]
+    /// Stamp with the physics frame time.
+    const MCAP_CLOCK_PHYSICS_FRAME: i64 = MCAPClockSource::PhysicsFrame as i64;
+    #[constant]
+    /// Stamp with `custom_clock`.
+    const MCAP_CLOCK_CUSTOM: i64 = MCAPClockSource::Custom as i64;
 }
 
 #[godot_api]
@@ -85,11 +110,12 @@ impl MCAPChannel {
 
 #[godot_api]
 impl MCAPMessage {
-    /// Create a message using the current engine time for log & publish timestamps.
+    /// Create a message stamped (log & publish time, in usec) with the clock of the most recently
+    /// opened writer (`MCAPWriteOptions.clock_source`; engine ticks before any writer is opened).
     /// The persisted timestamp may differ if the writer applies a timestamp offset.
     #[func]
     fn create(channel: Gd<MCAPChannel>, data: PackedByteArray) -> Gd<Self> {
-        let now = Time::singleton().get_ticks_usec();
+        let now = Clock::active_now_usec();
         let mut obj = Gd::from_object(Self {
             channel: OnEditor::default(),
             sequence: 0,
@@ -123,11 +149,12 @@ impl MCAPMessage {
 
 #[godot_api]
 impl MCAPAttachment {
-    /// Create an attachment using the current engine time for log & create timestamps.
+    /// Create an attachment stamped (log & create time, in usec) with the clock of the most recently
+    /// opened writer (`MCAPWriteOptions.clock_source`; engine ticks before any writer is opened).
     /// The persisted timestamps may differ if the writer applies a timestamp offset.
     #[func]
     fn create(name: GString, media_type: GString, data: PackedByteArray) -> Gd<Self> {
-        let now = Time::singleton().get_ticks_usec();
+        let now = Clock::active_now_usec();
         Gd::from_object(Self {
             log_time: now as i64,
             create_time: now as i64,
@@ -158,11 +185,12 @@ impl MCAPAttachment {
 
 #[godot_api]
 impl MCAPMessageHeader {
-    /// Create a message header using the current engine time for both timestamps.
-    /// The persisted timestamp may differ if the writer applies a timestamp offset.
+    /// Create a message header stamped (log & publish time, in usec) with the clock of the most
+    /// recently opened writer (`MCAPWriteOptions.clock_source`; engine ticks before any writer is
+    /// opened). The persisted timestamp may differ if the writer applies a timestamp offset.
     #[func]
     fn create(channel_id: i32) -> Gd<Self> {
-        let now = Time::singleton().get_ticks_usec();
+        let now = Clock::active_now_usec();
         Gd::from_object(Self {
             channel_id: channel_id as u16,
             sequence: 0,
@@ -117,15 +117,13 @@ impl MCAPChannel {
         }
     }
 
-    /// Create a Godot channel from an MCAP channel (cloning data as needed).
-    pub fn from_mcap(ch: &McapChannel) -> Gd<Self> {
+    /// Create a Godot channel from an MCAP channel (cloning data as needed), attaching `schema`
+    /// as its schema Resource.
+    pub fn from_mcap(ch: &McapChannel, schema: Option<Gd<MCAPSchema>>) -> Gd<Self> {
         Gd::from_object(Self {
             id: ch.id,
             topic: GString::from(ch.topic.as_str()),
-            schema: ch
-                .schema
-                .as_ref()
-                .map(|arc_schema| MCAPSchema::from_mcap(arc_schema.as_ref())),
+            schema,
             message_encoding: GString::from(ch.message_encoding.as_str()),
             metadata: btreemap_to_dict(&ch.metadata),
         })
@@ -150,9 +148,9 @@ impl MCAPMessage {
         })
     }
 
-    /// Create a Godot message. Since MCAP `Message` carries an `Arc<Channel>`,
-    /// we also build a Godot `MCAPChannel` and attach it.
-    pub fn from_mcap(msg: &McapMessage) -> Gd<Self> {
+    /// Create a Godot message published on `channel` (the Resource for `msg.channel`).
+    /// Timestamps are converted from the file's `unit` to microseconds.
+    pub fn from_mcap(msg: &McapMessage, channel: Gd<MCAPChannel>, unit: MCAPTimeUnit) -> Gd<Self> {
         let mut gd = Gd::from_object(Self {
             channel: OnEditor::default(),
             sequence: 0,
@@ -162,11 +160,10 @@ impl MCAPMessage {
         });
         {
             let mut b = gd.bind_mut();
-            let ch_gd = MCAPChannel::from_mcap(msg.channel.as_ref());
-            b.channel.init(ch_gd);
+            b.channel.init(channel);
             b.sequence = msg.sequence;
-            b.log_time = msg.log_time as i64;
-            b.publish_time = msg.publish_time as i64;
+            b.log_time = unit.to_usec(msg.log_time) as i64;
+            b.publish_time = unit.to_usec(msg.publish_time) as i64;
             b.data = PackedByteArray::from(match &msg.data {
                 Cow::Borrowed(s) => (*s).to_vec(),
                 Cow::Owned(v) => v.clone(),
@@ -193,10 +190,11 @@ impl MCAPAttachment {
     }
 
     /// Create a Godot attachment from an MCAP attachment (cloning data as needed).
-    pub fn from_mcap(att: &McapAttachment) -> Gd<Self> {
+    /// Timestamps are converted from the file's `unit` to microseconds.
+    pub fn from_mcap(att: &McapAttachment, unit: MCAPTimeUnit) -> Gd<Self> {
         Gd::from_object(Self {
-            log_time: att.log_time as i64,
-            create_time: att.create_time as i64,
+            log_time: unit.to_usec(att.log_time) as i64,
+            create_time: unit.to_usec(att.create_time) as i64,
             name: GString::from(att.name.as_str()),
             media_type: GString::from(att.media_type.as_str()),
             data: PackedByteArray::from(match &att.data {
@@ -222,12 +220,13 @@ impl MCAPMessageHeader {
     }
 
     /// Create a Godot message header from an MCAP message header (cloning data as needed).
-    pub fn from_mcap(header: &McapMessageHeader) -> Gd<Self> {
+    /// Timestamps are converted from the file's `unit` to microseconds.
+    pub fn from_mcap(header: &McapMessageHeader, unit: MCAPTimeUnit) -> Gd<Self> {
         Gd::from_object(Self {
             channel_id: header.channel_id,
             sequence: header.sequence,
-            log_time: header.log_time as i64,
-            publish_time: header.publish_time as i64,
+            log_time: unit.to_usec(header.log_time) as i64,
+            publish_time: unit.to_usec(header.publish_time) as i64,
         })
     }
 }
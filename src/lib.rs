@@ -1,5 +1,7 @@
 mod binary_stream;
 mod reader;
+mod recovery;
+mod time;
 mod util;
 mod writer;
 #[macro_use]
@@ -1,21 +1,276 @@
+use crate::reader::chunks::{ChunkCache, DecodedChunk};
+use godot::classes::file_access::ModeFlags;
 use godot::prelude::*;
-use std::sync::Arc;
+use godot::tools::GFile;
+use mcap::MAGIC;
+use mcap::read::{Summary, footer as mcap_footer};
+use mcap::records::{ChunkIndex, Footer};
+use mcap::sans_io::summary_reader::{SummaryReadEvent, SummaryReader, SummaryReaderOptions};
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::Deref;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+/// Size of the blocks read (and cached) by the FileAccess backend.
+const BLOCK_SIZE: u64 = 64 * 1024;
+/// Number of blocks the FileAccess backend keeps cached (2 MiB).
+const CACHED_BLOCKS: usize = 32;
+/// Footer record (opcode, length, summary start, summary offset start, CRC) plus end magic.
+const FOOTER_AND_MAGIC_LEN: u64 = 1 + 8 + 8 + 8 + 4 + 8;
 
 // Prefer on-demand access via memory mapping to avoid copying the whole file.
-// Fall back to an in-memory PackedByteArray when constructed from bytes or if mmap fails.
+// If mmap is not possible (e.g. inside a PCK, on Android or Web), read ranges through FileAccess on demand.
+// An in-memory PackedByteArray is used when constructed from bytes.
 pub(super) enum BufBackend {
     Memory(PackedByteArray),
     Mmap(memmap2::Mmap),
+    File(FileBuf),
 }
 
 impl BufBackend {
-    #[inline]
-    pub fn as_slice(&self) -> &[u8] {
+    /// Size of the file in bytes.
+    pub fn len(&self) -> u64 {
+        match self {
+            BufBackend::Memory(p) => p.len() as u64,
+            BufBackend::Mmap(m) => m.len() as u64,
+            BufBackend::File(f) => f.len,
+        }
+    }
+
+    /// The whole file as one slice, for linear scans. The FileAccess backend reads all of it.
+    pub fn contiguous(&self) -> Result<Cow<'_, [u8]>, String> {
+        self.read_at(0, self.len())
+    }
+
+    /// `len` bytes starting at `offset`; borrowed from memory, or read on demand from the file.
+    pub fn read_at(&self, offset: u64, len: u64) -> Result<Cow<'_, [u8]>, String> {
+        let end = offset
+            .checked_add(len)
+            .filter(|end| *end <= self.len())
+            .ok_or_else(|| {
+                format!(
+                    "read of {} bytes at offset {} is out of bounds (file has {} bytes)",
+                    len,
+                    offset,
+                    self.len()
+                )
+            })?;
         match self {
-            BufBackend::Memory(p) => p.as_slice(),
-            BufBackend::Mmap(m) => &m[..],
+            BufBackend::Memory(p) => {
+                Ok(Cow::Borrowed(&p.as_slice()[offset as usize..end as usize]))
+            }
+            BufBackend::Mmap(m) => Ok(Cow::Borrowed(&m[offset as usize..end as usize])),
+            BufBackend::File(f) => f.read(offset, end).map(Cow::Owned),
+        }
+    }
+
+    /// Read the summary section, if the file has one, seeking to it via the footer.
+    pub fn read_summary(&self) -> Result<Option<Summary>, String> {
+        let mut cursor = BufCursor { buf: self, pos: 0 };
+        let mut reader = SummaryReader::new_with_options(
+            SummaryReaderOptions::default().with_file_size(self.len()),
+        );
+        while let Some(event) = reader.next_event() {
+            match event.map_err(|e| e.to_string())? {
+                SummaryReadEvent::ReadRequest(n) => {
+                    let read = cursor.read(reader.insert(n)).map_err(|e| e.to_string())?;
+                    reader.notify_read(read);
+                }
+                SummaryReadEvent::SeekRequest(to) => {
+                    let pos = cursor.seek(to).map_err(|e| e.to_string())?;
+                    reader.notify_seeked(pos);
+                }
+            }
+        }
+        Ok(reader.finish())
+    }
+
+    /// Read the footer from the start and end of the file.
+    pub fn footer(&self) -> Result<Footer, String> {
+        let len = self.len();
+        let min_len = MAGIC.len() as u64 + FOOTER_AND_MAGIC_LEN;
+        if len <= min_len {
+            return mcap_footer(&self.contiguous()?).map_err(|e| e.to_string());
+        }
+        let mut ends = self.read_at(0, MAGIC.len() as u64)?.into_owned();
+        ends.extend_from_slice(&self.read_at(len - FOOTER_AND_MAGIC_LEN, FOOTER_AND_MAGIC_LEN)?);
+        mcap_footer(&ends).map_err(|e| e.to_string())
+    }
+
+    /// The chunk record described by `idx`, and `idx` rebased onto the returned bytes (for
+    /// `Summary::stream_chunk` and `Summary::seek_message`).
+    pub fn chunk(&self, idx: &ChunkIndex) -> Result<(Cow<'_, [u8]>, ChunkIndex), String> {
+        let bytes = self.read_at(idx.chunk_start_offset, idx.chunk_length)?;
+        let local = ChunkIndex {
+            chunk_start_offset: 0,
+            ..idx.clone()
+        };
+        Ok((bytes, local))
+    }
+
+    /// The message index records of the chunk described by `idx`, and `idx` rebased onto the
+    /// returned bytes (for `Summary::read_message_indexes`).
+    pub fn message_index_records(
+        &self,
+        idx: &ChunkIndex,
+    ) -> Result<(Cow<'_, [u8]>, ChunkIndex), String> {
+        let start = idx
+            .message_index_offsets
+            .values()
+            .min()
+            .copied()
+            .unwrap_or(0);
+        let bytes = self.read_at(start, idx.message_index_length)?;
+        let local = ChunkIndex {
+            message_index_offsets: idx
+                .message_index_offsets
+                .iter()
+                .map(|(id, offset)| (*id, offset - start))
+                .collect(),
+            ..idx.clone()
+        };
+        Ok((bytes, local))
+    }
+}
+
+/// Positioned reads through Godot's FileAccess, for files that can't be memory-mapped.
+///
+/// Small reads (records of the summary, footer, message indexes) go through an LRU cache of
+/// `BLOCK_SIZE` blocks; large ones (chunks, attachments) are read directly so memory stays
+/// bounded by the cache plus whatever the caller currently holds.
+pub(super) struct FileBuf {
+    state: Mutex<FileState>,
+    len: u64,
+}
+
+struct FileState {
+    file: GFile,
+    /// Cached blocks by block number, most recently used first.
+    blocks: VecDeque<(u64, Vec<u8>)>,
+}
+
+impl FileBuf {
+    pub fn open(path: &GString) -> Result<Self, String> {
+        let file = GFile::open(path, ModeFlags::READ)
+            .map_err(|e| format!("Failed to open {}: {}", path, e))?;
+        Ok(Self {
+            len: file.length(),
+            state: Mutex::new(FileState {
+                file,
+                blocks: VecDeque::new(),
+            }),
+        })
+    }
+
+    /// Read `[offset, end)`; the range must lie within the file.
+    fn read(&self, offset: u64, end: u64) -> Result<Vec<u8>, String> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if end - offset >= 2 * BLOCK_SIZE {
+            return state.read_exact_at(offset, end);
         }
+        let mut out = Vec::with_capacity((end - offset) as usize);
+        let mut pos = offset;
+        while pos < end {
+            let block = pos / BLOCK_SIZE;
+            let data = state.block(block, self.len)?;
+            let from = (pos - block * BLOCK_SIZE) as usize;
+            let to = data.len().min(from + (end - pos) as usize);
+            out.extend_from_slice(&data[from..to]);
+            pos += (to - from) as u64;
+        }
+        Ok(out)
+    }
+}
+
+impl FileState {
+    fn read_exact_at(&mut self, offset: u64, end: u64) -> Result<Vec<u8>, String> {
+        let len = (end - offset) as usize;
+        let mut out = vec![0; len];
+        self.file
+            .seek(SeekFrom::Start(offset))
+            .and_then(|_| self.file.read_exact(&mut out))
+            .map_err(|e| format!("reading {} bytes at offset {} failed: {}", len, offset, e))?;
+        Ok(out)
+    }
+
+    /// Contents of block number `block`, from the cache or the file.
+    fn block(&mut self, block: u64, file_len: u64) -> Result<&[u8], String> {
+        match self.blocks.iter().position(|(b, _)| *b == block) {
+            Some(i) => {
+                let entry = self.blocks.remove(i).unwrap();
+                self.blocks.push_front(entry);
+            }
+            None => {
+                let start = block * BLOCK_SIZE;
+                let data = self.read_exact_at(start, file_len.min(start + BLOCK_SIZE))?;
+                self.blocks.truncate(CACHED_BLOCKS - 1);
+                self.blocks.push_front((block, data));
+            }
+        }
+        Ok(&self.blocks[0].1)
+    }
+}
+
+/// `Read + Seek` view of a backend, used to drive the summary reader.
+struct BufCursor<'a> {
+    buf: &'a BufBackend,
+    pos: u64,
+}
+
+impl Read for BufCursor<'_> {
+    fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+        let n = (out.len() as u64).min(self.buf.len().saturating_sub(self.pos));
+        if n == 0 {
+            return Ok(0);
+        }
+        let bytes = self
+            .buf
+            .read_at(self.pos, n)
+            .map_err(std::io::Error::other)?;
+        out[..bytes.len()].copy_from_slice(&bytes);
+        self.pos += n;
+        Ok(n as usize)
+    }
+}
+
+impl Seek for BufCursor<'_> {
+    fn seek(&mut self, to: SeekFrom) -> std::io::Result<u64> {
+        let pos = match to {
+            SeekFrom::Start(p) => Some(p),
+            SeekFrom::End(d) => self.buf.len().checked_add_signed(d),
+            SeekFrom::Current(d) => self.pos.checked_add_signed(d),
+        };
+        self.pos = pos.ok_or_else(|| std::io::Error::other("seek before the start of the file"))?;
+        Ok(self.pos)
+    }
+}
+
+/// A reader's data, plus the cache of decompressed chunks shared by everything reading it.
+pub(super) struct ReaderBuf {
+    backend: BufBackend,
+    /// Kept (and shared) when the reader swaps in a new buffer for a grown file.
+    pub chunks: Arc<ChunkCache>,
+}
+
+impl ReaderBuf {
+    pub fn new(backend: BufBackend, chunks: Arc<ChunkCache>) -> SharedBuf {
+        Rc::new(Self { backend, chunks })
+    }
+
+    /// The decoded chunk described by `idx`, from the shared cache if possible.
+    pub fn decoded_chunk(&self, idx: &ChunkIndex) -> Result<Arc<DecodedChunk>, String> {
+        self.chunks.get(&self.backend, idx)
+    }
+}
+
+impl Deref for ReaderBuf {
+    type Target = BufBackend;
+
+    fn deref(&self) -> &BufBackend {
+        &self.backend
     }
 }
 
-pub type SharedBuf = Arc<BufBackend>;
+pub type SharedBuf = Rc<ReaderBuf>;
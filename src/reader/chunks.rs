@@ -0,0 +1,218 @@
+use crate::reader::buf::BufBackend;
+use crate::reader::index::chunk_record_len;
+use crate::reader::resources::ResourceCache;
+use crate::types::*;
+use godot::prelude::*;
+use mcap::read::{ChunkReader, Summary};
+use mcap::records::{ChunkIndex, MessageHeader, Record, op};
+use mcap::{McapError, Message, parse_record};
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// A message record of a decompressed chunk.
+pub(super) struct DecodedMessage {
+    /// Offset of the record within the chunk's uncompressed records (as in message indexes).
+    pub offset: u64,
+    pub header: MessageHeader,
+    pub data: Vec<u8>,
+}
+
+impl DecodedMessage {
+    /// Build the Godot message, resolving the channel through `summary` and its Resource through
+    /// `resources`.
+    pub fn to_godot(
+        &self,
+        summary: &Summary,
+        resources: &ResourceCache,
+        unit: MCAPTimeUnit,
+    ) -> Result<Gd<MCAPMessage>, String> {
+        let channel = summary
+            .channels
+            .get(&self.header.channel_id)
+            .ok_or_else(|| {
+                McapError::UnknownChannel(self.header.sequence, self.header.channel_id).to_string()
+            })?;
+        let msg = Message {
+            channel: channel.clone(),
+            sequence: self.header.sequence,
+            log_time: self.header.log_time,
+            publish_time: self.header.publish_time,
+            data: Cow::Borrowed(&self.data),
+        };
+        Ok(resources.message(&msg, unit))
+    }
+}
+
+/// The message records of one chunk, decompressed once so they can be revisited cheaply.
+pub(super) struct DecodedChunk {
+    /// Messages in record order (ascending offset).
+    pub messages: Vec<DecodedMessage>,
+    /// Approximate memory used, for the cache's size limit.
+    pub size: u64,
+}
+
+impl DecodedChunk {
+    pub fn decode(buf: &BufBackend, idx: &ChunkIndex) -> Result<Self, String> {
+        let bytes = buf.read_at(idx.chunk_start_offset, idx.chunk_length)?;
+        if bytes.len() < 9 {
+            return Err(McapError::BadIndex.to_string());
+        }
+        let (header, data) = match parse_record(op::CHUNK, &bytes[9..]) {
+            Ok(Record::Chunk { header, data }) => (header, data),
+            Ok(_) => return Err(McapError::BadIndex.to_string()),
+            Err(e) => return Err(e.to_string()),
+        };
+        let mut messages = Vec::new();
+        // Offsets are relative to the uncompressed records, which are re-measured from their fields
+        let mut offset = 0;
+        for record in ChunkReader::new(header, &data).map_err(|e| e.to_string())? {
+            let record = record.map_err(|e| e.to_string())?;
+            let len = chunk_record_len(&record).ok_or_else(|| McapError::BadIndex.to_string())?;
+            if let Record::Message { header, data } = record {
+                messages.push(DecodedMessage {
+                    offset,
+                    header,
+                    data: data.into_owned(),
+                });
+            }
+            offset += len;
+        }
+        let size = messages
+            .iter()
+            .map(|m| (std::mem::size_of::<DecodedMessage>() + m.data.len()) as u64)
+            .sum();
+        Ok(Self { messages, size })
+    }
+
+    /// The message record starting at `offset`, if any.
+    pub fn message_at(&self, offset: u64) -> Option<&DecodedMessage> {
+        self.messages
+            .binary_search_by_key(&offset, |m| m.offset)
+            .ok()
+            .map(|i| &self.messages[i])
+    }
+}
+
+/// Default size limit of a reader's chunk cache, in decompressed bytes.
+pub(super) const DEFAULT_CHUNK_CACHE_BYTES: u64 = 32 * 1024 * 1024;
+
+/// LRU of decoded chunks keyed by chunk start offset, owned by a reader's buffer and shared by
+/// its indexed queries, iterators and replays, so a chunk read by several of them (or revisited
+/// while scrubbing) is only decompressed once.
+pub(super) struct ChunkCache {
+    state: Mutex<CacheState>,
+}
+
+struct CacheState {
+    /// Most recently used first.
+    entries: VecDeque<(u64, Arc<DecodedChunk>)>,
+    bytes: u64,
+    max_bytes: u64,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+impl CacheState {
+    fn evict_to(&mut self, max_bytes: u64) {
+        while self.bytes > max_bytes {
+            let Some((_, chunk)) = self.entries.pop_back() else {
+                break;
+            };
+            self.bytes -= chunk.size;
+            self.evictions += 1;
+        }
+    }
+}
+
+/// Counters of a `ChunkCache`.
+pub(super) struct ChunkCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub entries: usize,
+    pub bytes: u64,
+}
+
+impl ChunkCache {
+    pub fn new(max_bytes: u64) -> Self {
+        Self {
+            state: Mutex::new(CacheState {
+                entries: VecDeque::new(),
+                bytes: 0,
+                max_bytes,
+                hits: 0,
+                misses: 0,
+                evictions: 0,
+            }),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// The decoded chunk described by `idx`, from the cache or decompressed from `buf`.
+    pub fn get(&self, buf: &BufBackend, idx: &ChunkIndex) -> Result<Arc<DecodedChunk>, String> {
+        let key = idx.chunk_start_offset;
+        {
+            let mut state = self.lock();
+            if let Some(i) = state.entries.iter().position(|(k, _)| *k == key) {
+                let entry = state.entries.remove(i).unwrap();
+                let chunk = entry.1.clone();
+                state.entries.push_front(entry);
+                state.hits += 1;
+                return Ok(chunk);
+            }
+            state.misses += 1;
+        }
+        // Decompress without holding the lock
+        let chunk = Arc::new(DecodedChunk::decode(buf, idx)?);
+        let mut state = self.lock();
+        if chunk.size <= state.max_bytes {
+            // Another consumer may have decoded the same chunk meanwhile
+            if let Some(i) = state.entries.iter().position(|(k, _)| *k == key) {
+                let (_, old) = state.entries.remove(i).unwrap();
+                state.bytes -= old.size;
+            }
+            state.bytes += chunk.size;
+            state.entries.push_front((key, chunk.clone()));
+            let max_bytes = state.max_bytes;
+            state.evict_to(max_bytes);
+        }
+        Ok(chunk)
+    }
+
+    pub fn max_bytes(&self) -> u64 {
+        self.lock().max_bytes
+    }
+
+    /// Change the size limit, evicting least recently used chunks as needed.
+    pub fn set_max_bytes(&self, max_bytes: u64) {
+        let mut state = self.lock();
+        state.max_bytes = max_bytes;
+        state.evict_to(max_bytes);
+    }
+
+    /// Drop all cached chunks and reset the counters.
+    pub fn clear(&self) {
+        let mut state = self.lock();
+        state.entries.clear();
+        state.bytes = 0;
+        state.hits = 0;
+        state.misses = 0;
+        state.evictions = 0;
+    }
+
+    pub fn stats(&self) -> ChunkCacheStats {
+        let state = self.lock();
+        ChunkCacheStats {
+            hits: state.hits,
+            misses: state.misses,
+            evictions: state.evictions,
+            entries: state.entries.len(),
+            bytes: state.bytes,
+        }
+    }
+}
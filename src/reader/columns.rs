@@ -0,0 +1,338 @@
+use crate::reader::buf::ReaderBuf;
+use crate::reader::filter::MsgFilter;
+use crate::types::*;
+use godot::prelude::*;
+use half::f16;
+use mcap::read::Summary;
+
+/// Scalar or vector stored at a fixed offset of every payload. Names match the `BinaryStream`
+/// read/write methods (`read_u16`, `read_vector3`, ...) and use the same little-endian encoding.
+#[derive(Clone, Copy)]
+enum FieldKind {
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+    Half,
+    F32,
+    F64,
+    Bool,
+    Vector2,
+    Vector3,
+    Vector4,
+    Quaternion,
+    Color,
+}
+
+impl FieldKind {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "u8" => Self::U8,
+            "u16" => Self::U16,
+            "u32" => Self::U32,
+            "u64" => Self::U64,
+            "i8" => Self::I8,
+            "i16" => Self::I16,
+            "i32" => Self::I32,
+            "i64" => Self::I64,
+            "half" => Self::Half,
+            "f32" => Self::F32,
+            "f64" => Self::F64,
+            "bool" => Self::Bool,
+            "vector2" => Self::Vector2,
+            "vector3" => Self::Vector3,
+            "vector4" => Self::Vector4,
+            "quaternion" => Self::Quaternion,
+            "color" => Self::Color,
+            _ => return None,
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Self::U8 | Self::I8 | Self::Bool => 1,
+            Self::U16 | Self::I16 | Self::Half => 2,
+            Self::U32 | Self::I32 | Self::F32 => 4,
+            Self::U64 | Self::I64 | Self::F64 | Self::Vector2 => 8,
+            Self::Vector3 => 12,
+            Self::Vector4 | Self::Quaternion | Self::Color => 16,
+        }
+    }
+
+    fn new_column(self) -> Column {
+        match self {
+            Self::U8
+            | Self::U16
+            | Self::U32
+            | Self::U64
+            | Self::I8
+            | Self::I16
+            | Self::I32
+            | Self::I64 => Column::Int(Vec::new()),
+            Self::Half | Self::F32 => Column::F32(Vec::new()),
+            Self::F64 => Column::F64(Vec::new()),
+            Self::Bool => Column::Bool(Vec::new()),
+            Self::Vector2 => Column::Vector2(Vec::new()),
+            Self::Vector3 => Column::Vector3(Vec::new()),
+            Self::Vector4 | Self::Quaternion => Column::Vector4(Vec::new()),
+            Self::Color => Column::Color(Vec::new()),
+        }
+    }
+}
+
+/// One entry of a payload layout.
+pub(super) struct FieldSpec {
+    name: GString,
+    kind: FieldKind,
+    offset: usize,
+}
+
+/// Parse a layout of `{ "name": String, "type": String, "offset": int (optional) }` entries. A
+/// field without `offset` starts where the previous one ended, as if written with `BinaryStream`.
+pub(super) fn parse_layout(layout: &Array<Dictionary>) -> Result<Vec<FieldSpec>, String> {
+    let mut fields = Vec::with_capacity(layout.len());
+    let mut next = 0usize;
+    for (i, entry) in layout.iter_shared().enumerate() {
+        let name = entry
+            .get("name")
+            .and_then(|v| v.try_to::<GString>().ok())
+            .filter(|n| !n.is_empty())
+            .ok_or_else(|| format!("layout field {} has no name", i))?;
+        let type_name = entry
+            .get("type")
+            .and_then(|v| v.try_to::<GString>().ok())
+            .ok_or_else(|| format!("layout field '{}' has no type", name))?;
+        let kind = FieldKind::parse(&type_name.to_string())
+            .ok_or_else(|| format!("layout field '{}' has unknown type '{}'", name, type_name))?;
+        let offset = match entry.get("offset") {
+            Some(v) => match v.try_to::<i64>() {
+                Ok(o) if o >= 0 => o as usize,
+                _ => return Err(format!("layout field '{}' has an invalid offset", name)),
+            },
+            None => next,
+        };
+        if fields.iter().any(|f: &FieldSpec| f.name == name) {
+            return Err(format!("layout field '{}' is listed twice", name));
+        }
+        next = offset
+            .checked_add(kind.size())
+            .ok_or_else(|| format!("layout field '{}' has an invalid offset", name))?;
+        fields.push(FieldSpec { name, kind, offset });
+    }
+    Ok(fields)
+}
+
+enum Column {
+    Int(Vec<i64>),
+    F32(Vec<f32>),
+    F64(Vec<f64>),
+    Bool(Vec<u8>),
+    Vector2(Vec<Vector2>),
+    Vector3(Vec<Vector3>),
+    Vector4(Vec<Vector4>),
+    Color(Vec<Color>),
+}
+
+fn le<const N: usize>(b: &[u8], at: usize) -> [u8; N] {
+    b[at..at + N].try_into().unwrap()
+}
+
+fn f32_at(b: &[u8], at: usize) -> f32 {
+    f32::from_le_bytes(le(b, at))
+}
+
+impl Column {
+    /// Append the field of `payload`, or a zero value if the payload is too short.
+    fn push(&mut self, field: &FieldSpec, payload: &[u8]) -> bool {
+        let ok = field
+            .offset
+            .checked_add(field.kind.size())
+            .is_some_and(|end| payload.len() >= end);
+        let b = payload;
+        let o = field.offset;
+        match self {
+            Self::Int(v) => v.push(match (ok, field.kind) {
+                (false, _) => 0,
+                (_, FieldKind::U8) => b[o] as i64,
+                (_, FieldKind::U16) => u16::from_le_bytes(le(b, o)) as i64,
+                (_, FieldKind::U32) => u32::from_le_bytes(le(b, o)) as i64,
+                (_, FieldKind::U64) => u64::from_le_bytes(le(b, o)) as i64,
+                (_, FieldKind::I8) => b[o] as i8 as i64,
+                (_, FieldKind::I16) => i16::from_le_bytes(le(b, o)) as i64,
+                (_, FieldKind::I32) => i32::from_le_bytes(le(b, o)) as i64,
+                _ => i64::from_le_bytes(le(b, o)),
+            }),
+            Self::F32(v) => v.push(match (ok, field.kind) {
+                (false, _) => 0.0,
+                (_, FieldKind::Half) => f16::from_le_bytes(le(b, o)).into(),
+                _ => f32_at(b, o),
+            }),
+            Self::F64(v) => v.push(if ok {
+                f64::from_le_bytes(le(b, o))
+            } else {
+                0.0
+            }),
+            Self::Bool(v) => v.push(u8::from(ok && b[o] != 0)),
+            Self::Vector2(v) => v.push(match ok {
+                true => Vector2::new(f32_at(b, o), f32_at(b, o + 4)),
+                false => Vector2::ZERO,
+            }),
+            Self::Vector3(v) => v.push(match ok {
+                true => Vector3::new(f32_at(b, o), f32_at(b, o + 4), f32_at(b, o + 8)),
+                false => Vector3::ZERO,
+            }),
+            Self::Vector4(v) => v.push(match ok {
+                true => Vector4::new(
+                    f32_at(b, o),
+                    f32_at(b, o + 4),
+                    f32_at(b, o + 8),
+                    f32_at(b, o + 12),
+                ),
+                false => Vector4::ZERO,
+            }),
+            Self::Color(v) => v.push(match ok {
+                true => Color::from_rgba(
+                    f32_at(b, o),
+                    f32_at(b, o + 4),
+                    f32_at(b, o + 8),
+                    f32_at(b, o + 12),
+                ),
+                false => Color::from_rgba(0.0, 0.0, 0.0, 0.0),
+            }),
+        }
+        ok
+    }
+
+    fn into_variant(self, order: Option<&[usize]>) -> Variant {
+        match self {
+            Self::Int(v) => PackedInt64Array::from(permuted(v, order)).to_variant(),
+            Self::F32(v) => PackedFloat32Array::from(permuted(v, order)).to_variant(),
+            Self::F64(v) => PackedFloat64Array::from(permuted(v, order)).to_variant(),
+            Self::Bool(v) => PackedByteArray::from(permuted(v, order)).to_variant(),
+            Self::Vector2(v) => PackedVector2Array::from(permuted(v, order)).to_variant(),
+            Self::Vector3(v) => PackedVector3Array::from(permuted(v, order)).to_variant(),
+            Self::Vector4(v) => PackedVector4Array::from(permuted(v, order)).to_variant(),
+            Self::Color(v) => PackedColorArray::from(permuted(v, order)).to_variant(),
+        }
+    }
+}
+
+/// `v` reordered so that element `i` is `v[order[i]]`.
+fn permuted<T: Copy>(v: Vec<T>, order: Option<&[usize]>) -> Vec<T> {
+    match order {
+        Some(order) => order.iter().map(|&i| v[i]).collect(),
+        None => v,
+    }
+}
+
+/// Collect the messages matching `filter` into columns, sorted by log time (stable, so messages
+/// with equal log times keep file order). Messages are never built as Resources.
+pub(super) fn extract(
+    buf: &ReaderBuf,
+    summary: &Summary,
+    filter: &MsgFilter,
+    unit: MCAPTimeUnit,
+    include_payload: bool,
+    layout: &[FieldSpec],
+) -> Result<Gd<MCAPColumns>, String> {
+    let mut channel_ids: Vec<i32> = Vec::new();
+    let mut log_times: Vec<i64> = Vec::new();
+    let mut publish_times: Vec<i64> = Vec::new();
+    let mut sequences: Vec<i32> = Vec::new();
+    let mut payload: Vec<u8> = Vec::new();
+    // start/end of every payload in `payload`
+    let mut spans: Vec<(usize, usize)> = Vec::new();
+    let mut columns: Vec<Column> = layout.iter().map(|f| f.kind.new_column()).collect();
+    let mut short_payloads = 0i64;
+    let mut sorted = true;
+
+    for chunk_idx in &summary.chunk_indexes {
+        if !filter.chunk_might_match(chunk_idx) {
+            continue;
+        }
+        let decoded = buf
+            .decoded_chunk(chunk_idx)
+            .map_err(|e| format!("decoding chunk failed: {}", e))?;
+        for msg in &decoded.messages {
+            if !filter.matches_time(msg.header.log_time) {
+                continue;
+            }
+            let channel_id = msg.header.channel_id;
+            if !filter.matches_ch(channel_id) {
+                continue;
+            }
+            let log_time = unit.to_usec(msg.header.log_time) as i64;
+            sorted &= log_times.last().is_none_or(|last| *last <= log_time);
+            channel_ids.push(channel_id as i32);
+            log_times.push(log_time);
+            publish_times.push(unit.to_usec(msg.header.publish_time) as i64);
+            sequences.push(msg.header.sequence as i32);
+            if include_payload {
+                spans.push((payload.len(), payload.len() + msg.data.len()));
+                payload.extend_from_slice(&msg.data);
+            }
+            let mut complete = true;
+            for (column, field) in columns.iter_mut().zip(layout) {
+                complete &= column.push(field, &msg.data);
+            }
+            if !complete {
+                short_payloads += 1;
+            }
+        }
+    }
+
+    let order: Option<Vec<usize>> = (!sorted).then(|| {
+        let mut order: Vec<usize> = (0..log_times.len()).collect();
+        order.sort_by_key(|&i| log_times[i]);
+        order
+    });
+    let order = order.as_deref();
+    let mut offsets: Vec<i64> = Vec::with_capacity(spans.len() + 1);
+    if include_payload {
+        offsets.push(0);
+        if let Some(order) = order {
+            let mut reordered = Vec::with_capacity(payload.len());
+            for &i in order {
+                let (start, end) = spans[i];
+                reordered.extend_from_slice(&payload[start..end]);
+                offsets.push(reordered.len() as i64);
+            }
+            payload = reordered;
+        } else {
+            offsets.extend(spans.iter().map(|(_, end)| *end as i64));
+        }
+    }
+    let mut fields = Dictionary::new();
+    for (column, field) in columns.into_iter().zip(layout) {
+        let _ = fields.insert(field.name.clone(), column.into_variant(order));
+    }
+
+    Ok(Gd::from_object(MCAPColumns {
+        channel_ids: PackedInt32Array::from(permuted(channel_ids, order)),
+        publish_times: PackedInt64Array::from(permuted(publish_times, order)),
+        sequences: PackedInt32Array::from(permuted(sequences, order)),
+        log_times: PackedInt64Array::from(permuted(log_times, order)),
+        payload: PackedByteArray::from(payload),
+        payload_offsets: PackedInt64Array::from(offsets),
+        fields,
+        short_payload_count: short_payloads,
+    }))
+}
+
+/// Columns without rows, returned on errors.
+pub(super) fn empty() -> Gd<MCAPColumns> {
+    Gd::from_object(MCAPColumns {
+        channel_ids: PackedInt32Array::new(),
+        log_times: PackedInt64Array::new(),
+        publish_times: PackedInt64Array::new(),
+        sequences: PackedInt32Array::new(),
+        payload: PackedByteArray::new(),
+        payload_offsets: PackedInt64Array::new(),
+        fields: Dictionary::new(),
+        short_payload_count: 0,
+    })
+}
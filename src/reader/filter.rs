@@ -1,9 +1,15 @@
+use crate::reader::buf::ReaderBuf;
+use crate::reader::predicate::MCAPMessagePredicate;
+use crate::reader::resources::ResourceCache;
 use crate::types::*;
+use godot::classes::RegEx;
 use godot::prelude::*;
+use mcap::Channel;
 use mcap::read::Summary;
 use std::collections::HashSet;
 
 // Reusable message filter for time range and channel sets
+#[derive(Clone)]
 pub(super) struct MsgFilter {
     pub time_start: Option<u64>,
     pub time_end: Option<u64>,
@@ -50,34 +56,93 @@ impl MsgFilter {
     }
 }
 
-// Shared helper: stream a chunk, apply filter, build MCAPMessage, and call a closure with (log_time, message)
+/// Channels selected by ID, topic name, topic glob or regex, or schema name. A channel is selected
+/// if any criterion matches it; an empty selection selects every channel.
+#[derive(Default, Clone)]
+pub(super) struct ChannelSelection {
+    pub ids: HashSet<u16>,
+    pub topics: HashSet<String>,
+    pub globs: Vec<GString>,
+    pub regexes: Vec<Gd<RegEx>>,
+    pub schemas: HashSet<String>,
+}
+
+impl ChannelSelection {
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+            && self.topics.is_empty()
+            && self.globs.is_empty()
+            && self.regexes.is_empty()
+            && self.schemas.is_empty()
+    }
+
+    pub fn matches(&self, channel: &Channel) -> bool {
+        if self.ids.contains(&channel.id) || self.topics.contains(&channel.topic) {
+            return true;
+        }
+        if channel
+            .schema
+            .as_ref()
+            .is_some_and(|s| self.schemas.contains(&s.name))
+        {
+            return true;
+        }
+        if self.globs.is_empty() && self.regexes.is_empty() {
+            return false;
+        }
+        let topic = GString::from(channel.topic.as_str());
+        self.globs.iter().any(|g| topic.match_glob(g))
+            || self.regexes.iter().any(|re| re.search(&topic).is_some())
+    }
+}
+
+/// Everything `stream_chunk_apply` needs from the reader: its bytes, summary, shared channel
+/// Resources and the file's time unit.
+pub(super) struct ChunkSource<'a> {
+    pub buf: &'a ReaderBuf,
+    pub summary: &'a Summary,
+    pub resources: &'a ResourceCache,
+    pub unit: MCAPTimeUnit,
+}
+
+// Shared helper: decode a chunk (through the reader's chunk cache), apply filter and predicate, build MCAPMessage (sharing the reader's channel Resources), and call a closure with (log_time, message).
+// Filter bounds and the log_time passed to `f` are in the file's unit; the message is converted to usec.
 pub(super) fn stream_chunk_apply<F>(
-    bytes: &[u8],
-    summary: &Summary,
+    source: &ChunkSource,
     chunk_idx: &mcap::records::ChunkIndex,
     filter: &MsgFilter,
+    predicate: Option<&MCAPMessagePredicate>,
     mut f: F,
 ) -> Result<(), String>
 where
     F: FnMut(u64, Gd<MCAPMessage>),
 {
-    let iter = summary
-        .stream_chunk(bytes, chunk_idx)
-        .map_err(|e| format!("stream_chunk open failed: {}", e))?;
-    for item in iter {
-        match item {
-            Ok(msg) => {
-                if !filter.matches_time(msg.log_time) {
-                    continue;
-                }
-                if !filter.matches_ch(msg.channel.id) {
-                    continue;
-                }
-                let gd = MCAPMessage::from_mcap(&msg);
-                f(msg.log_time, gd);
+    let summary = source.summary;
+    let decoded = source
+        .buf
+        .decoded_chunk(chunk_idx)
+        .map_err(|e| format!("decoding chunk failed: {}", e))?;
+    for msg in &decoded.messages {
+        if !filter.matches_time(msg.header.log_time) {
+            continue;
+        }
+        if !filter.matches_ch(msg.header.channel_id) {
+            continue;
+        }
+        if let Some(p) = predicate {
+            let encoding = summary
+                .channels
+                .get(&msg.header.channel_id)
+                .map_or("", |c| c.message_encoding.as_str());
+            if !p.matches_raw(encoding, &msg.data) {
+                continue;
             }
-            Err(e) => return Err(format!("stream_chunk failed: {}", e)),
         }
+        let gd = msg.to_godot(summary, source.resources, source.unit)?;
+        if predicate.is_some_and(|p| !p.matches_message(&gd)) {
+            continue;
+        }
+        f(msg.header.log_time, gd);
     }
     Ok(())
 }
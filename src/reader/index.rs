@@ -0,0 +1,552 @@
+use crate::reader::buf::BufBackend;
+use crate::recovery::Frames;
+use godot::prelude::*;
+use mcap::read::{ChunkReader, Summary};
+use mcap::records::{
+    AttachmentIndex, ChunkIndex, MessageIndexEntry, MetadataIndex, Record, Statistics,
+};
+use mcap::{Channel, McapError, Schema, parse_record};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+/// Identifies sidecar files written by `SyntheticIndex::to_dictionary`.
+const SIDECAR_FORMAT: &str = "godot-mcap-index";
+const SIDECAR_VERSION: i64 = 1;
+
+/// Message index entries of one chunk per channel, sorted by log time.
+pub(super) type ChunkMessageIndex = BTreeMap<u16, Vec<MessageIndexEntry>>;
+
+/// Summary synthesized by scanning the data section of a file that has no (usable) summary,
+/// together with the message indexes such files usually lack as well.
+#[derive(Clone)]
+pub(super) struct SyntheticIndex {
+    pub summary: Summary,
+    /// Message indexes keyed by the start offset of their chunk.
+    pub message_indexes: HashMap<u64, ChunkMessageIndex>,
+    /// Messages stored outside of chunks; they are counted but not reachable through the index.
+    pub unchunked_messages: u64,
+    /// Size of the file the index was built from (used to reject stale sidecars).
+    pub source_len: u64,
+    /// End of the last complete record indexed; `extend()` continues from here.
+    pub scanned_len: u64,
+}
+
+impl SyntheticIndex {
+    /// Scan the data section of `buf` once and index it.
+    ///
+    /// Chunks are decompressed to collect schemas, channels and message indexes. A truncated or
+    /// damaged tail ends the scan; everything before it stays indexed and is picked up by
+    /// `extend()` once it is complete.
+    pub fn build(buf: &[u8]) -> Result<Self, String> {
+        let mut index = Self::new();
+        index.extend(buf)?;
+        Ok(index)
+    }
+
+    /// An index of nothing yet; `extend()` scans the file from the start.
+    pub fn new() -> Self {
+        Self {
+            summary: Summary {
+                stats: Some(Statistics::default()),
+                ..Default::default()
+            },
+            message_indexes: HashMap::new(),
+            unchunked_messages: 0,
+            source_len: 0,
+            scanned_len: 0,
+        }
+    }
+
+    /// Index the records appended since the last scan. `tail` holds the file from `scanned_len`
+    /// on (the whole file for the first scan). Returns the number of newly indexed messages.
+    pub fn extend(&mut self, tail: &[u8]) -> Result<u64, String> {
+        let base = self.scanned_len;
+        let frames = match base {
+            0 => Frames::new(tail)?,
+            _ => Frames::resume(tail, 0),
+        };
+        let summary = &mut self.summary;
+        let mut stats = summary.stats.take().unwrap_or_default();
+        let messages_before = stats.message_count;
+        let mut time_range = match stats.message_count {
+            0 => (u64::MAX, 0),
+            _ => (stats.message_start_time, stats.message_end_time),
+        };
+
+        for frame in frames {
+            let Ok(frame) = frame else {
+                break;
+            };
+            let record = match parse_record(frame.opcode, frame.body) {
+                Ok(r) => r,
+                Err(_) => break,
+            };
+            let offset = base + frame.offset as u64;
+            let length = 9 + frame.body.len() as u64;
+            match record {
+                Record::Chunk { header, data } => {
+                    let Some((chunk_index, index, counts)) =
+                        index_chunk(summary, header, &data, offset, length)
+                    else {
+                        break;
+                    };
+                    if chunk_index.message_start_time <= chunk_index.message_end_time {
+                        time_range.0 = time_range.0.min(chunk_index.message_start_time);
+                        time_range.1 = time_range.1.max(chunk_index.message_end_time);
+                    }
+                    for (id, n) in counts {
+                        *stats.channel_message_counts.entry(id).or_default() += n;
+                        stats.message_count += n;
+                    }
+                    summary.chunk_indexes.push(chunk_index);
+                    self.message_indexes.insert(offset, index);
+                }
+                Record::Message { header, .. } => {
+                    self.unchunked_messages += 1;
+                    stats.message_count += 1;
+                    *stats
+                        .channel_message_counts
+                        .entry(header.channel_id)
+                        .or_default() += 1;
+                    time_range.0 = time_range.0.min(header.log_time);
+                    time_range.1 = time_range.1.max(header.log_time);
+                }
+                Record::Attachment { header, data, .. } => {
+                    summary.attachment_indexes.push(AttachmentIndex {
+                        offset,
+                        length,
+                        log_time: header.log_time,
+                        create_time: header.create_time,
+                        data_size: data.len() as u64,
+                        name: header.name,
+                        media_type: header.media_type,
+                    });
+                }
+                Record::Metadata(m) => {
+                    summary.metadata_indexes.push(MetadataIndex {
+                        offset,
+                        length,
+                        name: m.name,
+                    });
+                }
+                Record::DataEnd(_) | Record::Footer(_) => break,
+                r @ (Record::Schema { .. } | Record::Channel(_)) => {
+                    let Ok(()) = register(summary, r) else {
+                        break;
+                    };
+                }
+                _ => {}
+            }
+            self.scanned_len = offset + length;
+        }
+
+        stats.schema_count = summary.schemas.len() as u16;
+        stats.channel_count = summary.channels.len() as u32;
+        stats.attachment_count = summary.attachment_indexes.len() as u32;
+        stats.metadata_count = summary.metadata_indexes.len() as u32;
+        stats.chunk_count = summary.chunk_indexes.len() as u32;
+        if time_range.0 <= time_range.1 {
+            stats.message_start_time = time_range.0;
+            stats.message_end_time = time_range.1;
+        }
+        let new_messages = stats.message_count - messages_before;
+        summary.stats = Some(stats);
+        self.source_len = base + tail.len() as u64;
+        Ok(new_messages)
+    }
+
+    /// Serialize the index into a Dictionary (stored with `var_to_bytes` as a sidecar file).
+    pub fn to_dictionary(&self) -> Dictionary {
+        let mut schemas = VariantArray::new();
+        for schema in self.summary.schemas.values() {
+            let mut d = Dictionary::new();
+            d.set("id", schema.id);
+            d.set("name", schema.name.as_str());
+            d.set("encoding", schema.encoding.as_str());
+            d.set("data", PackedByteArray::from(&schema.data[..]));
+            schemas.push(&d.to_variant());
+        }
+        let mut channels = VariantArray::new();
+        for channel in self.summary.channels.values() {
+            let mut d = Dictionary::new();
+            d.set("id", channel.id);
+            d.set(
+                "schema_id",
+                channel.schema.as_ref().map(|s| s.id).unwrap_or(0),
+            );
+            d.set("topic", channel.topic.as_str());
+            d.set("message_encoding", channel.message_encoding.as_str());
+            d.set("metadata", string_map_to_dict(&channel.metadata));
+            channels.push(&d.to_variant());
+        }
+        let mut chunks = VariantArray::new();
+        for idx in &self.summary.chunk_indexes {
+            let mut d = Dictionary::new();
+            d.set("message_start_time", idx.message_start_time as i64);
+            d.set("message_end_time", idx.message_end_time as i64);
+            d.set("chunk_start_offset", idx.chunk_start_offset as i64);
+            d.set("chunk_length", idx.chunk_length as i64);
+            d.set("compression", idx.compression.as_str());
+            d.set("compressed_size", idx.compressed_size as i64);
+            d.set("uncompressed_size", idx.uncompressed_size as i64);
+            // channel id -> [log_time, offset, log_time, offset, ...]
+            let mut entries = Dictionary::new();
+            if let Some(index) = self.message_indexes.get(&idx.chunk_start_offset) {
+                for (id, list) in index {
+                    let flat: Vec<i64> = list
+                        .iter()
+                        .flat_map(|e| [e.log_time as i64, e.offset as i64])
+                        .collect();
+                    entries.set(*id, PackedInt64Array::from(&flat[..]));
+                }
+            }
+            d.set("message_indexes", entries);
+            chunks.push(&d.to_variant());
+        }
+        let mut attachments = VariantArray::new();
+        for idx in &self.summary.attachment_indexes {
+            let mut d = Dictionary::new();
+            d.set("offset", idx.offset as i64);
+            d.set("length", idx.length as i64);
+            d.set("log_time", idx.log_time as i64);
+            d.set("create_time", idx.create_time as i64);
+            d.set("data_size", idx.data_size as i64);
+            d.set("name", idx.name.as_str());
+            d.set("media_type", idx.media_type.as_str());
+            attachments.push(&d.to_variant());
+        }
+        let mut metadata = VariantArray::new();
+        for idx in &self.summary.metadata_indexes {
+            let mut d = Dictionary::new();
+            d.set("offset", idx.offset as i64);
+            d.set("length", idx.length as i64);
+            d.set("name", idx.name.as_str());
+            metadata.push(&d.to_variant());
+        }
+        let mut stats = Dictionary::new();
+        if let Some(st) = &self.summary.stats {
+            stats.set("message_count", st.message_count as i64);
+            stats.set("message_start_time", st.message_start_time as i64);
+            stats.set("message_end_time", st.message_end_time as i64);
+            let mut counts = Dictionary::new();
+            for (id, n) in &st.channel_message_counts {
+                counts.set(*id, *n as i64);
+            }
+            stats.set("channel_message_counts", counts);
+        }
+
+        let mut out = Dictionary::new();
+        out.set("format", SIDECAR_FORMAT);
+        out.set("version", SIDECAR_VERSION);
+        out.set("source_len", self.source_len as i64);
+        out.set("scanned_len", self.scanned_len as i64);
+        out.set("unchunked_messages", self.unchunked_messages as i64);
+        out.set("schemas", schemas);
+        out.set("channels", channels);
+        out.set("chunks", chunks);
+        out.set("attachments", attachments);
+        out.set("metadata", metadata);
+        out.set("stats", stats);
+        out
+    }
+
+    /// Inverse of `to_dictionary`.
+    pub fn from_dictionary(d: &Dictionary) -> Result<Self, String> {
+        if field::<GString>(d, "format")?.to_string() != SIDECAR_FORMAT {
+            return Err("not a godot-mcap index".into());
+        }
+        let version = field::<i64>(d, "version")?;
+        if version != SIDECAR_VERSION {
+            return Err(format!("unsupported index version {}", version));
+        }
+
+        let mut summary = Summary::default();
+        for s in dicts(d, "schemas")? {
+            let schema = Schema {
+                id: field(&s, "id")?,
+                name: field::<GString>(&s, "name")?.to_string(),
+                encoding: field::<GString>(&s, "encoding")?.to_string(),
+                data: Cow::Owned(field::<PackedByteArray>(&s, "data")?.to_vec()),
+            };
+            summary.schemas.insert(schema.id, Arc::new(schema));
+        }
+        for c in dicts(d, "channels")? {
+            let schema = match field::<u16>(&c, "schema_id")? {
+                0 => None,
+                id => Some(
+                    summary
+                        .schemas
+                        .get(&id)
+                        .cloned()
+                        .ok_or_else(|| format!("channel references unknown schema {}", id))?,
+                ),
+            };
+            let channel = Channel {
+                id: field(&c, "id")?,
+                topic: field::<GString>(&c, "topic")?.to_string(),
+                schema,
+                message_encoding: field::<GString>(&c, "message_encoding")?.to_string(),
+                metadata: dict_to_string_map(&field(&c, "metadata")?),
+            };
+            summary.channels.insert(channel.id, Arc::new(channel));
+        }
+
+        let mut message_indexes = HashMap::new();
+        for c in dicts(d, "chunks")? {
+            let idx = ChunkIndex {
+                message_start_time: u64_field(&c, "message_start_time")?,
+                message_end_time: u64_field(&c, "message_end_time")?,
+                chunk_start_offset: u64_field(&c, "chunk_start_offset")?,
+                chunk_length: u64_field(&c, "chunk_length")?,
+                message_index_offsets: BTreeMap::new(),
+                message_index_length: 0,
+                compression: field::<GString>(&c, "compression")?.to_string(),
+                compressed_size: u64_field(&c, "compressed_size")?,
+                uncompressed_size: u64_field(&c, "uncompressed_size")?,
+            };
+            let mut index = ChunkMessageIndex::new();
+            for (id, flat) in field::<Dictionary>(&c, "message_indexes")?.iter_shared() {
+                let id = id.try_to::<u16>().map_err(|e| e.to_string())?;
+                let flat = flat
+                    .try_to::<PackedInt64Array>()
+                    .map_err(|e| e.to_string())?;
+                let entries = flat
+                    .as_slice()
+                    .chunks_exact(2)
+                    .map(|p| MessageIndexEntry {
+                        log_time: p[0] as u64,
+                        offset: p[1] as u64,
+                    })
+                    .collect();
+                index.insert(id, entries);
+            }
+            message_indexes.insert(idx.chunk_start_offset, index);
+            summary.chunk_indexes.push(idx);
+        }
+        for a in dicts(d, "attachments")? {
+            summary.attachment_indexes.push(AttachmentIndex {
+                offset: u64_field(&a, "offset")?,
+                length: u64_field(&a, "length")?,
+                log_time: u64_field(&a, "log_time")?,
+                create_time: u64_field(&a, "create_time")?,
+                data_size: u64_field(&a, "data_size")?,
+                name: field::<GString>(&a, "name")?.to_string(),
+                media_type: field::<GString>(&a, "media_type")?.to_string(),
+            });
+        }
+        for m in dicts(d, "metadata")? {
+            summary.metadata_indexes.push(MetadataIndex {
+                offset: u64_field(&m, "offset")?,
+                length: u64_field(&m, "length")?,
+                name: field::<GString>(&m, "name")?.to_string(),
+            });
+        }
+
+        let st = field::<Dictionary>(d, "stats")?;
+        let mut stats = Statistics {
+            message_count: u64_field(&st, "message_count")?,
+            schema_count: summary.schemas.len() as u16,
+            channel_count: summary.channels.len() as u32,
+            attachment_count: summary.attachment_indexes.len() as u32,
+            metadata_count: summary.metadata_indexes.len() as u32,
+            chunk_count: summary.chunk_indexes.len() as u32,
+            message_start_time: u64_field(&st, "message_start_time")?,
+            message_end_time: u64_field(&st, "message_end_time")?,
+            channel_message_counts: BTreeMap::new(),
+        };
+        for (id, n) in field::<Dictionary>(&st, "channel_message_counts")?.iter_shared() {
+            let id = id.try_to::<u16>().map_err(|e| e.to_string())?;
+            let n = n.try_to::<i64>().map_err(|e| e.to_string())?;
+            stats.channel_message_counts.insert(id, n as u64);
+        }
+        summary.stats = Some(stats);
+
+        Ok(Self {
+            summary,
+            message_indexes,
+            unchunked_messages: u64_field(d, "unchunked_messages")?,
+            source_len: u64_field(d, "source_len")?,
+            scanned_len: u64_field(d, "scanned_len")?,
+        })
+    }
+}
+
+/// Message indexes of `chunk`: read from the file when it has them, else taken from `synthetic`.
+pub(super) fn read_message_indexes<'s>(
+    summary: &'s Summary,
+    synthetic: Option<&SyntheticIndex>,
+    buf: &BufBackend,
+    chunk: &ChunkIndex,
+) -> Result<HashMap<Arc<Channel<'s>>, Vec<MessageIndexEntry>>, String> {
+    let index = match synthetic {
+        Some(s) if chunk.message_index_offsets.is_empty() => s
+            .message_indexes
+            .get(&chunk.chunk_start_offset)
+            .ok_or_else(|| McapError::BadIndex.to_string())?,
+        _ => {
+            let (bytes, local) = buf.message_index_records(chunk)?;
+            return summary
+                .read_message_indexes(&bytes, &local)
+                .map_err(|e| e.to_string());
+        }
+    };
+    let mut out = HashMap::new();
+    for (id, entries) in index {
+        let channel = summary
+            .channels
+            .get(id)
+            .ok_or_else(|| McapError::UnknownChannel(0, *id).to_string())?;
+        out.insert(channel.clone(), entries.clone());
+    }
+    Ok(out)
+}
+
+/// True if message indexes for `chunk` are available, in the file or in `synthetic`.
+pub(super) fn has_message_indexes(synthetic: Option<&SyntheticIndex>, chunk: &ChunkIndex) -> bool {
+    !chunk.message_index_offsets.is_empty()
+        || synthetic.is_some_and(|s| s.message_indexes.contains_key(&chunk.chunk_start_offset))
+}
+
+/// Index one chunk: returns its chunk index, message index and per-channel message counts, or
+/// None if the chunk can't be decoded.
+fn index_chunk(
+    summary: &mut Summary,
+    header: mcap::records::ChunkHeader,
+    data: &[u8],
+    offset: u64,
+    length: u64,
+) -> Option<(ChunkIndex, ChunkMessageIndex, BTreeMap<u16, u64>)> {
+    let mut chunk_index = ChunkIndex {
+        message_start_time: header.message_start_time,
+        message_end_time: header.message_end_time,
+        chunk_start_offset: offset,
+        chunk_length: length,
+        message_index_offsets: BTreeMap::new(),
+        message_index_length: 0,
+        compression: header.compression.clone(),
+        compressed_size: header.compressed_size,
+        uncompressed_size: header.uncompressed_size,
+    };
+    let mut index = ChunkMessageIndex::new();
+    let mut counts: BTreeMap<u16, u64> = BTreeMap::new();
+    let mut time_range = (u64::MAX, 0);
+    // Offsets are relative to the uncompressed records, which are re-measured from their fields
+    let mut record_offset = 0;
+    for record in ChunkReader::new(header, data).ok()? {
+        let record = record.ok()?.into_owned();
+        let len = chunk_record_len(&record)?;
+        match record {
+            Record::Message { header, .. } => {
+                index
+                    .entry(header.channel_id)
+                    .or_default()
+                    .push(MessageIndexEntry {
+                        log_time: header.log_time,
+                        offset: record_offset,
+                    });
+                *counts.entry(header.channel_id).or_default() += 1;
+                time_range.0 = time_range.0.min(header.log_time);
+                time_range.1 = time_range.1.max(header.log_time);
+            }
+            r => register(summary, r).ok()?,
+        }
+        record_offset += len;
+    }
+    for entries in index.values_mut() {
+        entries.sort_by_key(|e| e.log_time);
+    }
+    // Writers that were interrupted may not have filled in the chunk's time range
+    if time_range.0 <= time_range.1 {
+        chunk_index.message_start_time = time_range.0;
+        chunk_index.message_end_time = time_range.1;
+    }
+    Some((chunk_index, index, counts))
+}
+
+/// Serialized length (opcode, length prefix and body) of a record found inside a chunk.
+pub(super) fn chunk_record_len(record: &Record) -> Option<u64> {
+    let body = match record {
+        Record::Schema { header, data } => {
+            2 + 4 + header.name.len() + 4 + header.encoding.len() + 4 + data.len()
+        }
+        Record::Channel(c) => {
+            2 + 2
+                + 4
+                + c.topic.len()
+                + 4
+                + c.message_encoding.len()
+                + 4
+                + c.metadata
+                    .iter()
+                    .map(|(k, v)| 8 + k.len() + v.len())
+                    .sum::<usize>()
+        }
+        Record::Message { data, .. } => 2 + 4 + 8 + 8 + data.len(),
+        _ => return None,
+    };
+    Some(9 + body as u64)
+}
+
+/// Add a schema or channel record to `summary`.
+fn register(summary: &mut Summary, record: Record) -> Result<(), ()> {
+    match record.into_owned() {
+        Record::Schema { header, data } => {
+            let schema = Schema {
+                id: header.id,
+                name: header.name,
+                encoding: header.encoding,
+                data,
+            };
+            summary.schemas.insert(schema.id, Arc::new(schema));
+        }
+        Record::Channel(ch) => {
+            let schema = match ch.schema_id {
+                0 => None,
+                id => Some(summary.schemas.get(&id).ok_or(())?.clone()),
+            };
+            let channel = Channel {
+                id: ch.id,
+                topic: ch.topic,
+                schema,
+                message_encoding: ch.message_encoding,
+                metadata: ch.metadata,
+            };
+            summary.channels.insert(channel.id, Arc::new(channel));
+        }
+        _ => return Err(()),
+    }
+    Ok(())
+}
+
+fn field<T: FromGodot>(d: &Dictionary, key: &str) -> Result<T, String> {
+    d.get(key)
+        .ok_or_else(|| format!("missing '{}'", key))?
+        .try_to::<T>()
+        .map_err(|e| format!("invalid '{}': {}", key, e))
+}
+
+fn u64_field(d: &Dictionary, key: &str) -> Result<u64, String> {
+    field::<i64>(d, key).map(|v| v as u64)
+}
+
+fn dicts(d: &Dictionary, key: &str) -> Result<Vec<Dictionary>, String> {
+    field::<VariantArray>(d, key)?
+        .iter_shared()
+        .map(|v| v.try_to::<Dictionary>().map_err(|e| e.to_string()))
+        .collect()
+}
+
+fn string_map_to_dict(map: &BTreeMap<String, String>) -> Dictionary {
+    let mut d = Dictionary::new();
+    for (k, v) in map {
+        d.set(k.as_str(), v.as_str());
+    }
+    d
+}
+
+fn dict_to_string_map(d: &Dictionary) -> BTreeMap<String, String> {
+    d.iter_shared()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
@@ -1,27 +1,44 @@
 use crate::reader::buf::SharedBuf;
-use crate::reader::filter::{MsgFilter, stream_chunk_apply};
+use crate::reader::chunks::{DecodedChunk, DecodedMessage};
+use crate::reader::filter::{ChannelSelection, MsgFilter};
+use crate::reader::index::{SyntheticIndex, has_message_indexes, read_message_indexes};
 use crate::reader::mcap_reader::MCAPReader;
+use crate::reader::multi_reader::IdMap;
+use crate::reader::predicate::MCAPMessagePredicate;
+use crate::reader::resources::SharedResources;
 use crate::types::*;
+use godot::classes::RegEx;
 use godot::prelude::*;
+use mcap::McapError;
 use mcap::read::Summary;
-use std::collections::HashSet;
+use mcap::records::{ChunkIndex, MessageIndexEntry};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(GodotClass)]
 /// Iterator for streaming MCAP messages using summary indexes.
 ///
 /// Overview
-/// - Obtained from `MCAPReader.stream_messages_iterator()`.
-/// - Iterates messages in log-time order across chunks and channels.
-/// - Supports optional per-channel filtering and multiple seek helpers.
+/// - Obtained from `MCAPReader.stream_messages_iterator()` or `MCAPMultiReader.stream_messages_iterator()`.
+/// - Iterates messages in log-time order across chunks and channels (and across files for a multi reader),
+///   also when chunks overlap in time.
+/// - Supports channel/topic/schema filters, a log-time window, multiple seek helpers and other orderings (`order`).
 /// - Requires a Summary section in the file.
-/// - Identity note: messages and their nested channel/schema are newly constructed per iteration step.
-///   Compare by fields (e.g. `msg.channel.id`, `msg.sequence`, `msg.log_time`) rather than by object identity.
+/// - Identity note: messages are newly constructed per iteration step, but their `channel` (and its
+///   `schema`) is the Resource shared by the reader (or multi reader session), so channels can be
+///   compared by identity. Compare messages by fields (e.g. `msg.channel.id`, `msg.sequence`, `msg.log_time`).
 ///
 /// Usage (GDScript)
 /// ```gdscript
 /// var it := reader.stream_messages_iterator()
 /// # Optionally restrict to a channel id:
 /// it.for_channel(42)
+/// # ...or to several topics and a time window (criteria add up):
+/// it.for_topics(PackedStringArray(["/imu", "/gps"]))
+/// it.for_topic_pattern("/camera/*")
+/// it.set_time_window(1_000_000, 5_000_000)
 ///
 /// # Simple for-in iteration:
 /// for msg in it:
@@ -32,59 +49,303 @@ use std::collections::HashSet;
 /// var ok := it.seek_to_time_nearest(500_000) # nearest at or before 0.5s if none after
 /// var ok2 := it.seek_to_next_on_channel(42, 2_000_000) # first message on ch 42 after 2.0s
 /// var exact := it.get_message_at_time(42, 2_500_000) # message on ch 42 exactly at time, if any
+/// var state := it.latest_messages_at(3_000_000) # latest message per channel at or before 3.0s
 ///
 /// # Manual stepping:
 /// while it.has_next_message():
 ///     var msg := it.get_next_message()
 ///     if msg:
 ///         print("next:", msg.log_time)
+///
+/// # Step back one message:
+/// var prev := it.get_prev_message()
+///
+/// # Newest first:
+/// it.reverse = true
+/// for msg in it:
+///     print(msg.log_time)
+///
+/// # Messages as stored in the file instead:
+/// it.order = MCAPMessageIterator.ORDER_FILE
 /// ```
 ///
+/// Ordering (`order`)
+/// - `ORDER_LOG_TIME` (default): by log time. Chunks are loaded lazily once their
+///   `message_start_time` is reached and merged with the other loaded chunks, so only chunks that
+///   overlap the current time are held in memory. Messages with equal log times keep file order.
+/// - `ORDER_PUBLISH_TIME`: by publish time. Chunk indexes only record log times, so chunks are
+///   still loaded by log time; a message published before the log time of a chunk that has not
+///   been loaded yet may be yielded late.
+/// - `ORDER_FILE`: as stored, one chunk at a time in summary order (and file after file for a
+///   multi reader).
+/// - Seeking always refers to log times: it positions the iterator between the messages before
+///   that log time and those at or after it.
+///
+/// Backwards
+/// - The iterator stands between two messages. `get_next_message()` moves forward past one,
+///   `get_prev_message()` moves back past one (so right after `get_next_message()` it returns the
+///   same message again); `peek_prev_message()`/`has_prev_message()` mirror their forward versions.
+/// - With `reverse` enabled, `for msg in it` runs from the last message to the first.
+/// - Going backwards loads chunks from their end times down, with the same merging, filtering and
+///   bounded memory as going forward; changing direction only reloads the chunks around the
+///   current position (usually from the reader's chunk cache). `seek_to_end()` positions after
+///   the last message.
+///
+/// Filtering
+/// - `for_channels()`, `for_topics()`, `for_topic_pattern()` (glob), `for_topic_regex()` and
+///   `for_schemas()` add to one selection: a channel is included if any criterion matches it.
+///   `for_channel()` replaces the selection with a single id; `clear_filter()` empties it.
+/// - Topic and schema criteria are resolved to channel ids when iteration starts (and again when
+///   follow mode picks up new channels), so filtering costs the same as filtering by id.
+/// - `set_time_window()` restricts messages to a log-time range; chunks outside it are never
+///   loaded and seeks stay inside it.
+/// - `set_predicate()` adds a condition on message contents (see `MCAPMessagePredicate`), checked
+///   after the filters above. Native JSON predicates run on the raw payload; Callable predicates
+///   get the built message and must not use this iterator.
+/// - Changing filters rewinds the iterator.
+///
+/// Follow mode
+/// - With `follow` enabled, an exhausted iterator picks up chunks the reader has indexed since
+///   (see `MCAPReader.refresh()`), so iteration can continue on a file that is still being written.
+///
 /// Notes
-/// - The iterator buffers messages per-chunk and merges them by log_time.
-/// - Using `for_channel()` before iteration applies an efficient filter for a single channel.
-/// - All time values are microseconds (usec).
+/// - For an `MCAPMultiReader`, channel ids (in messages and in `for_channel()` etc.) are the merged ones.
+/// - Chunks without messages matching the channel selection and time window (according to their
+///   message indexes) are not decompressed.
+/// - Seek helpers and `get_message_at_time()` search the per-chunk message indexes instead of
+///   decoding chunks, and messages are only built when they are returned. Decompressed chunks
+///   come from the reader's chunk cache, so scrubbing back and forth stays cheap.
+/// - All time values are microseconds (usec); files in other units are converted using the
+///   reader's `time_unit` at the time the iterator was created.
 #[class(no_init, base=RefCounted)]
 pub struct MCAPMessageIterator {
-    // immutable input
-    pub(super) buf: SharedBuf,
-    filter_channel: Option<u16>,
+    // one source per file (several for an MCAPMultiReader)
+    sources: Vec<Source>,
+    // channels to yield (empty: all), and the IDs they resolve to in the sources' summaries
+    selection: ChannelSelection,
+    channels: Option<HashSet<u16>>,
+    // inclusive log time window (usec); None: unbounded on that side
+    window: (Option<u64>, Option<u64>),
+    // content condition checked before a message is yielded
+    predicate: Option<Gd<MCAPMessagePredicate>>,
+    /// If true, continue with data the reader indexed after this iterator ran out.
+    #[var]
+    pub(super) follow: bool,
+    /// Order in which messages are yielded; changing it rewinds the iterator.
+    #[var(get, set = set_order)]
+    order: MCAPMessageOrder,
+    /// If true, `for` loops run backwards, from the last message to the first.
+    #[var]
+    reverse: bool,
+    // position between two messages; iteration in either direction continues from here
+    gap: Gap,
+    // direction `pending`/`loaded` were set up for; None if they must be rebuilt from `gap`
+    merge_dir: Option<Direction>,
+    // chunks not loaded yet, by the first order key they may yield
+    pending: BinaryHeap<PendingChunk>,
+    // loaded chunks with messages left, by their next message
+    loaded: BinaryHeap<ChunkCursor>,
     // iterator state
     index: i64,
-    peek: Option<Gd<MCAPMessage>>, // next element ready for _iter_get
-    pub(super) summary: Option<Summary>,
-    chunk_i: usize,
-    // per-chunk buffered messages sorted by log_time
-    chunk_msgs: Vec<(u64, Gd<MCAPMessage>)>,
-    chunk_pos: usize,
+    peek: Option<(Direction, Gd<MCAPMessage>, Pos)>, // next element ready for _iter_get
 }
 
-impl MCAPMessageIterator {
-    pub(super) fn new_from_reader(reader: &MCAPReader, filter_channel: Option<u16>) -> Gd<Self> {
-        Gd::from_object(Self {
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Direction {
+    Forward,
+    Backward,
+}
+
+/// Place of a message in iteration order: (order key, source, chunk, index in the chunk).
+/// Iteration yields messages by ascending (forward) or descending (backward) position.
+type Pos = (u64, usize, usize, usize);
+
+/// Where the iterator stands: between two messages.
+#[derive(Clone, Copy, Debug)]
+enum Gap {
+    Start,
+    End,
+    /// Before the first message with log time >= the given usec (set by seeking).
+    Time(u64),
+    After(Pos),
+    Before(Pos),
+}
+
+impl Gap {
+    /// Log time bounds (file unit, inclusive) of the messages lying in `dir` from here;
+    /// None if there are none.
+    fn time_bounds(self, dir: Direction, unit: MCAPTimeUnit) -> Option<(Option<u64>, Option<u64>)> {
+        match (self, dir) {
+            (Gap::Start, Direction::Backward) | (Gap::End, Direction::Forward) => None,
+            (Gap::Time(t), Direction::Forward) => Some((Some(unit.usec_to_unit_ceil(t)), None)),
+            (Gap::Time(t), Direction::Backward) => match unit.usec_to_unit_ceil(t) {
+                0 => None,
+                t => Some((None, Some(t - 1))),
+            },
+            _ => Some((None, None)),
+        }
+    }
+
+    /// True unless the message at `pos` lies on the other side of a message position.
+    fn admits(self, dir: Direction, pos: Pos) -> bool {
+        match (self, dir) {
+            (Gap::After(p), Direction::Forward) => pos > p,
+            (Gap::After(p), Direction::Backward) => pos <= p,
+            (Gap::Before(p), Direction::Forward) => pos >= p,
+            (Gap::Before(p), Direction::Backward) => pos < p,
+            _ => true,
+        }
+    }
+}
+
+/// A chunk waiting to be loaded; the heap yields the one whose messages come first in `dir`.
+struct PendingChunk {
+    dir: Direction,
+    // (first order key it may yield, source, chunk)
+    key: (u64, usize, usize),
+}
+
+/// A decompressed chunk and its matching messages as (order key, index into `messages`), in
+/// yield order.
+type LoadedChunk = (Arc<DecodedChunk>, Vec<(u64, usize)>);
+
+/// The remaining messages of a loaded chunk, ordered by its next message.
+struct ChunkCursor {
+    dir: Direction,
+    source: usize,
+    chunk: usize,
+    decoded: Arc<DecodedChunk>,
+    // (order key, index into `decoded.messages`) in yield order
+    order: Vec<(u64, usize)>,
+    pos: usize,
+}
+
+impl ChunkCursor {
+    fn key(&self) -> (u64, usize, usize) {
+        (self.order[self.pos].0, self.source, self.chunk)
+    }
+}
+
+/// Heap order (BinaryHeap pops the greatest): the smallest key first going forward, the
+/// largest going backward.
+fn heap_order<K: Ord>(dir: Direction, a: K, b: K) -> Ordering {
+    match dir {
+        Direction::Forward => b.cmp(&a),
+        Direction::Backward => a.cmp(&b),
+    }
+}
+
+impl PartialEq for PendingChunk {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for PendingChunk {}
+
+impl PartialOrd for PendingChunk {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PendingChunk {
+    fn cmp(&self, other: &Self) -> Ordering {
+        heap_order(self.dir, self.key, other.key)
+    }
+}
+
+impl PartialEq for ChunkCursor {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for ChunkCursor {}
+
+impl PartialOrd for ChunkCursor {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ChunkCursor {
+    fn cmp(&self, other: &Self) -> Ordering {
+        heap_order(self.dir, self.key(), other.key())
+    }
+}
+
+/// One file read by an iterator.
+struct Source {
+    // input; refreshed from the reader in follow mode
+    reader: Gd<MCAPReader>,
+    buf: SharedBuf,
+    summary: Option<Summary>,
+    synthetic: Option<Arc<SyntheticIndex>>,
+    // merged channel/schema IDs when read through an MCAPMultiReader
+    ids: Option<Rc<IdMap>>,
+    // channel/schema Resources messages are built with (the session's for an MCAPMultiReader)
+    resources: SharedResources,
+    // unit of the file's timestamps; public times are usec
+    time_unit: MCAPTimeUnit,
+    // number of chunk indexes already queued for loading (or skipped)
+    queued: usize,
+}
+
+/// Channel ID a message is reported with (the merged one for an MCAPMultiReader).
+fn channel_id(summary: &Summary, m: &DecodedMessage) -> u16 {
+    summary
+        .channels
+        .get(&m.header.channel_id)
+        .map_or(m.header.channel_id, |c| c.id)
+}
+
+impl Source {
+    fn new(reader: &MCAPReader, ids: Option<Rc<IdMap>>, resources: SharedResources) -> Self {
+        let mut source = Self {
+            reader: reader.to_gd(),
             buf: reader.buf.clone(),
-            filter_channel,
-            index: 0,
-            peek: None,
             summary: None,
-            chunk_i: 0,
-            chunk_msgs: Vec::new(),
-            chunk_pos: 0,
-        })
+            synthetic: reader.synthetic.clone(),
+            ids,
+            resources,
+            time_unit: reader.time_unit,
+            queued: 0,
+        };
+        source.summary = reader.summary.as_ref().map(|s| source.view(s));
+        source
     }
 
-    fn reset_iteration_state(&mut self) {
-        self.index = 0;
-        self.peek = None;
-        self.chunk_i = 0;
-        self.chunk_msgs.clear();
-        self.chunk_pos = 0;
+    /// The summary as seen by this iterator (with merged IDs, if any).
+    fn view(&self, summary: &Summary) -> Summary {
+        match &self.ids {
+            Some(ids) => ids.apply(summary),
+            None => summary.clone(),
+        }
+    }
+
+    fn chunk_count(&self) -> usize {
+        self.summary.as_ref().map_or(0, |s| s.chunk_indexes.len())
+    }
+
+    /// Adopt the reader's current buffer and index; returns true if chunks were added.
+    fn sync_with_reader(&mut self) -> bool {
+        let reader = self.reader.clone();
+        let reader = reader.bind();
+        let chunks = reader.summary.as_ref().map_or(0, |s| s.chunk_indexes.len());
+        if chunks <= self.chunk_count() {
+            return false;
+        }
+        self.buf = reader.buf.clone();
+        self.summary = reader.summary.as_ref().map(|s| self.view(s));
+        self.synthetic = reader.synthetic.clone();
+        true
     }
 
     fn ensure_summary(&mut self) -> bool {
         if self.summary.is_none() {
-            match Summary::read(self.buf.as_slice()) {
-                Ok(opt) => self.summary = opt,
+            match self.buf.read_summary() {
+                Ok(opt) => self.summary = opt.map(|s| self.view(&s)),
                 Err(e) => {
                     godot_error!("MCAPMessageIterator: reading summary failed: {}", e);
                     self.summary = None;
@@ -94,148 +355,759 @@ impl MCAPMessageIterator {
         self.summary.is_some()
     }
 
-    fn prepare_next_chunk(&mut self) -> bool {
-        let Some(summary) = &self.summary else {
-            return false;
+    /// True if some chunk may hold messages at or after `t_usec`.
+    fn has_chunk_ending_at_or_after(&self, t_usec: u64) -> bool {
+        let t = self.time_unit.usec_to_unit_ceil(t_usec);
+        self.summary
+            .as_ref()
+            .is_some_and(|s| s.chunk_indexes.iter().any(|ch| ch.message_end_time >= t))
+    }
+
+    /// Decompress chunk `chunk_i` and list the messages matching `filter` as (order key, index)
+    /// in yield order; None if there are none.
+    fn load_chunk(
+        &self,
+        chunk_i: usize,
+        filter: &MsgFilter,
+        order: MCAPMessageOrder,
+    ) -> Option<LoadedChunk> {
+        let summary = self.summary.as_ref()?;
+        let chunk_idx = &summary.chunk_indexes[chunk_i];
+        // With a channel or time filter, the message indexes tell whether decompressing is worth it
+        let narrowed =
+            filter.channels.is_some() || filter.time_start.is_some() || filter.time_end.is_some();
+        if narrowed && has_message_indexes(self.synthetic.as_deref(), chunk_idx) {
+            match self.index_entries(summary, chunk_idx, filter) {
+                Ok(entries) if !entries.iter().any(|e| filter.matches_time(e.log_time)) => {
+                    return None;
+                }
+                Ok(_) => {}
+                Err(e) => godot_error!("MCAPMessageIterator: {}", e),
+            }
+        }
+        let decoded = match self.buf.decoded_chunk(chunk_idx) {
+            Ok(decoded) => decoded,
+            Err(e) => {
+                godot_error!("MCAPMessageIterator: {}", e);
+                return None;
+            }
         };
-        while self.chunk_i < summary.chunk_indexes.len() {
-            let chunk_idx = &summary.chunk_indexes[self.chunk_i];
-            self.chunk_msgs.clear();
-            self.chunk_pos = 0;
-            let filter = MsgFilter {
-                time_start: None,
-                time_end: None,
-                channels: self.filter_channel.map(|id| {
-                    let mut s = HashSet::new();
-                    s.insert(id);
-                    s
-                }),
-            };
-            if let Err(e) =
-                stream_chunk_apply(self.buf.as_slice(), summary, chunk_idx, &filter, |t, gd| {
-                    self.chunk_msgs.push((t, gd))
-                })
+        let unit = self.time_unit;
+        let mut keys = Vec::new();
+        for (i, m) in decoded.messages.iter().enumerate() {
+            if !filter.matches_time(m.header.log_time) || !filter.matches_ch(channel_id(summary, m))
             {
+                continue;
+            }
+            let key = match order {
+                MCAPMessageOrder::LogTime => unit.to_usec(m.header.log_time),
+                MCAPMessageOrder::PublishTime => unit.to_usec(m.header.publish_time),
+                MCAPMessageOrder::File => 0,
+            };
+            keys.push((key, i));
+        }
+        // stable, so messages with equal keys keep file order
+        keys.sort_by_key(|(key, _)| *key);
+        (!keys.is_empty()).then_some((decoded, keys))
+    }
+
+    /// Message encoding of the channel `m` was published on.
+    fn encoding(&self, m: &DecodedMessage) -> &str {
+        self.summary
+            .as_ref()
+            .and_then(|s| s.channels.get(&m.header.channel_id))
+            .map_or("", |c| c.message_encoding.as_str())
+    }
+
+    /// Build the Godot message for a record of a loaded chunk.
+    fn message(&self, m: &DecodedMessage) -> Option<Gd<MCAPMessage>> {
+        let summary = self.summary.as_ref()?;
+        match m.to_godot(summary, &self.resources, self.time_unit) {
+            Ok(msg) => Some(msg),
+            Err(e) => {
                 godot_error!("MCAPMessageIterator: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Index entries (file-unit log time and offset) of the messages in `chunk_idx` on channels
+    /// matching `filter`. Taken from the message indexes when available, so the chunk itself is
+    /// only decompressed for files without them.
+    fn index_entries(
+        &self,
+        summary: &Summary,
+        chunk_idx: &ChunkIndex,
+        filter: &MsgFilter,
+    ) -> Result<Vec<MessageIndexEntry>, String> {
+        Ok(self
+            .channel_entries(summary, chunk_idx, filter)?
+            .into_iter()
+            .map(|(_, entry)| entry)
+            .collect())
+    }
+
+    /// Like `index_entries()`, with the channel ID of each entry.
+    fn channel_entries(
+        &self,
+        summary: &Summary,
+        chunk_idx: &ChunkIndex,
+        filter: &MsgFilter,
+    ) -> Result<Vec<(u16, MessageIndexEntry)>, String> {
+        if has_message_indexes(self.synthetic.as_deref(), chunk_idx) {
+            let map =
+                read_message_indexes(summary, self.synthetic.as_deref(), &self.buf, chunk_idx)?;
+            return Ok(map
+                .into_iter()
+                .filter(|(ch, _)| filter.matches_ch(ch.id))
+                .flat_map(|(ch, entries)| entries.into_iter().map(move |e| (ch.id, e)))
+                .collect());
+        }
+        let decoded = self.buf.decoded_chunk(chunk_idx)?;
+        Ok(decoded
+            .messages
+            .iter()
+            .map(|m| (channel_id(summary, m), m))
+            .filter(|(ch, _)| filter.matches_ch(*ch))
+            .map(|(ch, m)| {
+                let entry = MessageIndexEntry {
+                    log_time: m.header.log_time,
+                    offset: m.offset,
+                };
+                (ch, entry)
+            })
+            .collect())
+    }
+
+    /// The latest message at or before `t` (file unit) on each channel matching `filter`. Chunks
+    /// are visited from the latest end time down and only their message indexes are read until
+    /// every channel that can have such a message has been found; just the returned messages are
+    /// decompressed.
+    fn latest_per_channel(&self, t: u64, filter: &MsgFilter) -> Vec<Gd<MCAPMessage>> {
+        let Some(summary) = self.summary.as_ref() else {
+            return Vec::new();
+        };
+        let mut chunks: Vec<usize> = (0..summary.chunk_indexes.len())
+            .filter(|&ci| summary.chunk_indexes[ci].message_start_time <= t)
+            .collect();
+        chunks.sort_by_key(|&ci| std::cmp::Reverse(summary.chunk_indexes[ci].message_end_time));
+        let wanted = self.channels_until(summary, &chunks, filter);
+        // channel -> (log time, chunk, offset) of the latest message so far
+        let mut best: HashMap<u16, (u64, usize, u64)> = HashMap::new();
+        for ci in chunks {
+            let chunk_idx = &summary.chunk_indexes[ci];
+            // Once every channel has a message, older chunks can't hold a later one
+            if wanted.iter().all(|ch| best.contains_key(ch))
+                && best.values().all(|b| chunk_idx.message_end_time < b.0)
+            {
+                break;
+            }
+            let entries = match self.channel_entries(summary, chunk_idx, filter) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    godot_error!("MCAPMessageIterator: {}", e);
+                    continue;
+                }
+            };
+            for (ch, e) in entries {
+                if e.log_time > t || !filter.matches_time(e.log_time) {
+                    continue;
+                }
+                let candidate = (e.log_time, ci, e.offset);
+                // equal log times: the last one in file order wins
+                if best.get(&ch).is_none_or(|b| candidate > *b) {
+                    best.insert(ch, candidate);
+                }
+            }
+        }
+        best.into_values()
+            .filter_map(|(_, ci, offset)| {
+                let msg = self
+                    .buf
+                    .decoded_chunk(&summary.chunk_indexes[ci])
+                    .and_then(|decoded| {
+                        decoded
+                            .message_at(offset)
+                            .ok_or_else(|| McapError::BadIndex.to_string())
+                            .and_then(|m| m.to_godot(summary, &self.resources, self.time_unit))
+                    });
+                msg.map_err(|e| godot_error!("MCAPMessageIterator: {}", e))
+                    .ok()
+            })
+            .collect()
+    }
+
+    /// Channels matching `filter` that can have a message in `chunks` (the chunks starting by the
+    /// seek time). Taken from the chunks' message indexes; without them, every channel that has
+    /// messages at all according to the statistics.
+    fn channels_until(
+        &self,
+        summary: &Summary,
+        chunks: &[usize],
+        filter: &MsgFilter,
+    ) -> HashSet<u16> {
+        let synthetic = self.synthetic.as_deref();
+        let mut ids = HashSet::new();
+        for &ci in chunks {
+            let chunk_idx = &summary.chunk_indexes[ci];
+            let indexed =
+                synthetic.and_then(|s| s.message_indexes.get(&chunk_idx.chunk_start_offset));
+            if !chunk_idx.message_index_offsets.is_empty() {
+                ids.extend(chunk_idx.message_index_offsets.keys());
+            } else if let Some(index) = indexed {
+                ids.extend(index.keys());
             } else {
-                self.chunk_msgs.sort_by_key(|(t, _)| *t);
-                if !self.chunk_msgs.is_empty() {
-                    return true;
+                ids = match summary.stats.as_ref() {
+                    Some(st) if !st.channel_message_counts.is_empty() => st
+                        .channel_message_counts
+                        .iter()
+                        .filter(|(_, n)| **n > 0)
+                        .map(|(id, _)| *id)
+                        .collect(),
+                    _ => summary.channels.keys().copied().collect(),
+                };
+                break;
+            }
+        }
+        ids.into_iter()
+            .map(|id| summary.channels.get(&id).map_or(id, |c| c.id))
+            .filter(|id| filter.matches_ch(*id))
+            .collect()
+    }
+
+    // Find the latest message time at or before t (file unit).
+    fn find_nearest_at_or_before(&self, t: u64, filter: &MsgFilter) -> Option<u64> {
+        let summary = self.summary.as_ref()?;
+        let mut best: Option<u64> = None;
+        for chunk_idx in &summary.chunk_indexes {
+            // chunks may overlap, so every chunk starting early enough is a candidate
+            if chunk_idx.message_start_time > t
+                || best.is_some_and(|bt| chunk_idx.message_end_time <= bt)
+            {
+                continue;
+            }
+            let Ok(entries) = self.index_entries(summary, chunk_idx, filter) else {
+                continue;
+            };
+            for e in entries {
+                if e.log_time <= t
+                    && filter.matches_time(e.log_time)
+                    && best.map(|bt| e.log_time > bt).unwrap_or(true)
+                {
+                    best = Some(e.log_time);
                 }
             }
-            self.chunk_i += 1;
         }
-        false
+        best
     }
 
-    fn next_message_internal(&mut self) -> Option<Gd<MCAPMessage>> {
-        if !self.ensure_summary() {
-            return None;
+    // Find the earliest message time strictly after t (file unit) matching `filter`.
+    fn find_first_after(&self, t: u64, filter: &MsgFilter) -> Option<u64> {
+        let summary = self.summary.as_ref()?;
+        let mut best: Option<u64> = None;
+        for chunk_idx in &summary.chunk_indexes {
+            // chunks may overlap, so a later chunk can still hold an earlier message
+            if chunk_idx.message_end_time <= t
+                || best.is_some_and(|bt| chunk_idx.message_start_time >= bt)
+            {
+                continue;
+            }
+            let Ok(entries) = self.index_entries(summary, chunk_idx, filter) else {
+                continue;
+            };
+            for e in entries {
+                if e.log_time > t
+                    && filter.matches_time(e.log_time)
+                    && best.map(|bt| e.log_time < bt).unwrap_or(true)
+                {
+                    best = Some(e.log_time);
+                }
+            }
         }
-        loop {
-            if self.chunk_msgs.is_empty() {
-                // Load first available chunk
-                if !self.prepare_next_chunk() {
+        best
+    }
+
+    /// The message on `ch_id` whose log time maps exactly to `t_usec`, if any.
+    fn message_at_time(&self, ch_id: u16, t_usec: u64) -> Option<Gd<MCAPMessage>> {
+        let unit = self.time_unit;
+        // first file time that maps to t_usec; the entry found there must map back exactly
+        let t = unit.usec_to_unit_ceil(t_usec);
+        let summary = self.summary.as_ref()?;
+        let filter = MsgFilter {
+            time_start: None,
+            time_end: None,
+            channels: Some(HashSet::from([ch_id])),
+        };
+        for chunk_idx in &summary.chunk_indexes {
+            if t < chunk_idx.message_start_time || t > chunk_idx.message_end_time {
+                continue;
+            }
+            let entries = match self.index_entries(summary, chunk_idx, &filter) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    godot_error!("get_message_at_time: reading message indexes failed: {}", e);
                     return None;
                 }
-            }
-            if self.chunk_pos >= self.chunk_msgs.len() {
-                // Finished current chunk; move to next
-                self.chunk_i += 1;
-                if !self.prepare_next_chunk() {
+            };
+            let Some(entry) = entries
+                .iter()
+                .filter(|e| e.log_time >= t)
+                .min_by_key(|e| (e.log_time, e.offset))
+                .filter(|e| unit.to_usec(e.log_time) == t_usec)
+            else {
+                continue;
+            };
+            // Only the returned message is materialized
+            let msg = self.buf.decoded_chunk(chunk_idx).and_then(|decoded| {
+                decoded
+                    .message_at(entry.offset)
+                    .ok_or_else(|| McapError::BadIndex.to_string())
+                    .and_then(|m| m.to_godot(summary, &self.resources, unit))
+            });
+            match msg {
+                Ok(msg) => return Some(msg),
+                Err(e) => {
+                    godot_error!("get_message_at_time: {}", e);
                     return None;
                 }
             }
-            if self.chunk_pos < self.chunk_msgs.len() {
-                let msg = self.chunk_msgs[self.chunk_pos].1.clone();
-                self.chunk_pos += 1;
-                return Some(msg);
+        }
+        None
+    }
+}
+
+impl MCAPMessageIterator {
+    pub(super) fn new_from_reader(reader: &MCAPReader) -> Gd<Self> {
+        let resources = reader.resources.clone();
+        Self::new_with_sources(vec![Source::new(reader, None, resources)])
+    }
+
+    /// Iterator over several files, merged by log time; `ids` maps each file to merged IDs and
+    /// `resources` holds the session's channel/schema Resources.
+    pub(super) fn new_merged(
+        readers: Vec<(Gd<MCAPReader>, Option<Rc<IdMap>>)>,
+        resources: SharedResources,
+    ) -> Gd<Self> {
+        let sources = readers
+            .into_iter()
+            .map(|(reader, ids)| Source::new(&reader.bind(), ids, resources.clone()))
+            .collect();
+        Self::new_with_sources(sources)
+    }
+
+    fn new_with_sources(sources: Vec<Source>) -> Gd<Self> {
+        Gd::from_object(Self {
+            sources,
+            selection: ChannelSelection::default(),
+            channels: None,
+            window: (None, None),
+            predicate: None,
+            follow: false,
+            order: MCAPMessageOrder::LogTime,
+            reverse: false,
+            gap: Gap::Start,
+            merge_dir: None,
+            pending: BinaryHeap::new(),
+            loaded: BinaryHeap::new(),
+            index: 0,
+            peek: None,
+        })
+    }
+
+    /// Move to `gap`; chunks are queued again lazily by the next step in either direction.
+    fn restart(&mut self, gap: Gap) {
+        self.index = 0;
+        self.peek = None;
+        self.gap = gap;
+        self.merge_dir = None;
+    }
+
+    fn reset_iteration_state(&mut self) {
+        self.restart(Gap::Start);
+    }
+
+    /// Filter applied while loading chunks of a file in `unit`: the selected channels and the
+    /// time window.
+    fn chunk_filter(&self, unit: MCAPTimeUnit) -> MsgFilter {
+        MsgFilter {
+            time_start: self.window.0.map(|t| unit.usec_to_unit_ceil(t)),
+            time_end: self.window.1.map(|t| unit.usec_to_unit_saturating(t)),
+            channels: self.channels.clone(),
+        }
+    }
+
+    /// Resolve the channel selection against the channels the sources know so far.
+    fn resolve_channels(&mut self) {
+        if self.selection.is_empty() {
+            self.channels = None;
+            return;
+        }
+        let mut ids = HashSet::new();
+        for summary in self.sources.iter().filter_map(|s| s.summary.as_ref()) {
+            for channel in summary.channels.values() {
+                if self.selection.matches(channel) {
+                    ids.insert(channel.id);
+                }
             }
         }
+        self.channels = Some(ids);
     }
 
-    // Load chunk at index and position to first message with time >= t; if no such message in this chunk, advance to next non-empty chunk.
-    fn load_and_seek_at_or_after(&mut self, chunk_index: usize, t: u64) -> bool {
-        self.reset_iteration_state();
-        self.chunk_i = chunk_index;
-        if !self.prepare_next_chunk() {
+    /// Make sure every source has its summary; returns true if any has one.
+    fn ensure_summary(&mut self) -> bool {
+        let mut any = false;
+        for source in self.sources.iter_mut() {
+            any |= source.ensure_summary();
+        }
+        any
+    }
+
+    /// True if chunk `ci` of source `si`, spanning log times `[start, end]` (usec), may hold
+    /// messages lying in `dir` from the current gap.
+    fn chunk_may_match(&self, dir: Direction, si: usize, ci: usize, start: u64, end: u64) -> bool {
+        if self.window.0.is_some_and(|t| end < t) || self.window.1.is_some_and(|t| start > t) {
             return false;
         }
-        let pos = match self.chunk_msgs.binary_search_by_key(&t, |(lt, _)| *lt) {
-            Ok(i) => i,
-            Err(i) => i,
-        };
-        if pos < self.chunk_msgs.len() {
-            self.chunk_pos = pos;
-            true
-        } else {
-            self.chunk_i += 1;
-            if !self.prepare_next_chunk() {
-                return false;
+        let forward = dir == Direction::Forward;
+        match self.gap {
+            Gap::Start => forward,
+            Gap::End => !forward,
+            Gap::Time(t) if forward => end >= t,
+            Gap::Time(t) => start < t,
+            Gap::After(p) | Gap::Before(p) => match self.order {
+                MCAPMessageOrder::LogTime if forward => end >= p.0,
+                MCAPMessageOrder::LogTime => start <= p.0,
+                MCAPMessageOrder::File if forward => (si, ci) >= (p.1, p.2),
+                MCAPMessageOrder::File => (si, ci) <= (p.1, p.2),
+                // Chunk indexes don't bound publish times
+                MCAPMessageOrder::PublishTime => true,
+            },
+        }
+    }
+
+    /// Queue the chunks of every source that were not considered yet.
+    fn queue_new_chunks(&mut self, dir: Direction) {
+        let follow = self.follow;
+        let mut queue = Vec::new();
+        for (si, source) in self.sources.iter_mut().enumerate() {
+            if follow && source.summary.is_none() {
+                // Nothing indexed yet (e.g. the file is still empty); wait for the reader
+                source.sync_with_reader();
+                if source.summary.is_none() {
+                    continue;
+                }
+            }
+            if !source.ensure_summary() {
+                continue;
             }
-            self.chunk_pos = 0;
-            !self.chunk_msgs.is_empty()
+            let Some(summary) = &source.summary else {
+                continue;
+            };
+            let unit = source.time_unit;
+            for (ci, chunk_idx) in summary.chunk_indexes.iter().enumerate().skip(source.queued) {
+                let start = unit.to_usec(chunk_idx.message_start_time);
+                let end = unit.to_usec(chunk_idx.message_end_time);
+                queue.push((si, ci, start, end));
+            }
+            source.queued = summary.chunk_indexes.len();
+        }
+        for (si, ci, start, end) in queue {
+            if !self.chunk_may_match(dir, si, ci, start, end) {
+                continue;
+            }
+            // Chunk indexes only carry log times; publish times are assumed to follow them
+            let key = match (self.order, dir) {
+                (MCAPMessageOrder::File, _) => 0,
+                (_, Direction::Forward) => start,
+                (_, Direction::Backward) => end,
+            };
+            self.pending.push(PendingChunk {
+                dir,
+                key: (key, si, ci),
+            });
         }
     }
 
-    // Find the nearest message time at or before t and return (chunk_index, time).
-    fn find_nearest_at_or_before(&self, t: u64) -> Option<(usize, u64)> {
-        let summary = self.summary.as_ref()?;
-        let bytes = self.buf.as_slice();
-        let mut best: Option<(usize, u64)> = None;
-        let filter = MsgFilter {
-            time_start: None,
-            time_end: Some(t),
-            channels: self.filter_channel.map(|id| {
-                let mut s = HashSet::new();
-                s.insert(id);
-                s
-            }),
-        };
-        for (i, chunk_idx) in summary.chunk_indexes.iter().enumerate() {
-            if chunk_idx.message_start_time > t {
+    /// Load pending chunks that may hold a message ordered before the current head.
+    fn load_due_chunks(&mut self, dir: Direction) {
+        while let Some(&PendingChunk {
+            key: (key, si, ci), ..
+        }) = self.pending.peek()
+        {
+            let due = match self.loaded.peek() {
+                None => true,
+                // in file order, the next chunk starts once the current one is done
+                Some(_) if self.order == MCAPMessageOrder::File => false,
+                Some(head) if dir == Direction::Forward => key <= head.key().0,
+                Some(head) => key >= head.key().0,
+            };
+            if !due {
                 break;
             }
-            let _ = stream_chunk_apply(bytes, summary, chunk_idx, &filter, |time, _gd| {
-                if best.map(|(_, bt)| time > bt).unwrap_or(true) {
-                    best = Some((i, time));
+            self.pending.pop();
+            let source = &self.sources[si];
+            let Some((time_start, time_end)) = self.gap.time_bounds(dir, source.time_unit) else {
+                continue;
+            };
+            // Narrow the time window to the messages on this side of the gap
+            let mut filter = self.chunk_filter(source.time_unit);
+            filter.time_start = filter.time_start.max(time_start);
+            filter.time_end = match (filter.time_end, time_end) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            let Some((decoded, mut order)) = source.load_chunk(ci, &filter, self.order) else {
+                continue;
+            };
+            let gap = self.gap;
+            order.retain(|&(key, i)| gap.admits(dir, (key, si, ci, i)));
+            if dir == Direction::Backward {
+                order.reverse();
+            }
+            if !order.is_empty() {
+                self.loaded.push(ChunkCursor {
+                    dir,
+                    source: si,
+                    chunk: ci,
+                    decoded,
+                    order,
+                    pos: 0,
+                });
+            }
+        }
+    }
+
+    /// The next message in `dir` from the merge state (rebuilt from `gap` if it was set up for
+    /// the other direction), with its position.
+    fn next_in(&mut self, dir: Direction) -> Option<(Gd<MCAPMessage>, Pos)> {
+        let rebuild = self.merge_dir != Some(dir);
+        if rebuild {
+            self.merge_dir = Some(dir);
+            self.pending.clear();
+            self.loaded.clear();
+            for source in self.sources.iter_mut() {
+                source.queued = 0;
+            }
+        }
+        self.queue_new_chunks(dir);
+        if rebuild {
+            self.resolve_channels();
+        }
+        loop {
+            self.load_due_chunks(dir);
+            if let Some(mut cursor) = self.loaded.pop() {
+                // Messages are only materialized when they are yielded
+                let (key, i) = cursor.order[cursor.pos];
+                let pos = (key, cursor.source, cursor.chunk, i);
+                let m = &cursor.decoded.messages[i];
+                let source = &self.sources[cursor.source];
+                // Messages failing the predicate are dropped here, before reaching script
+                let msg = match &self.predicate {
+                    Some(p) => p
+                        .bind()
+                        .filter(source.encoding(m), &m.data, || source.message(m)),
+                    None => source.message(m),
+                };
+                cursor.pos += 1;
+                if cursor.pos < cursor.order.len() {
+                    self.loaded.push(cursor);
                 }
-            });
+                match msg {
+                    Some(msg) => return Some((msg, pos)),
+                    None => continue,
+                }
+            }
+            // Exhausted; in follow mode, continue with chunks the reader has indexed since
+            let mut grew = false;
+            if self.follow && dir == Direction::Forward {
+                for source in self.sources.iter_mut() {
+                    grew |= source.sync_with_reader();
+                }
+            }
+            if !grew {
+                return None;
+            }
+            self.queue_new_chunks(dir);
+            // New channels may match the selection
+            self.resolve_channels();
         }
-        best
+    }
+
+    /// The message next to the current position in `dir`, without consuming it.
+    fn peek_in(&mut self, dir: Direction) -> Option<Gd<MCAPMessage>> {
+        if self.peek.as_ref().is_some_and(|(d, ..)| *d != dir) {
+            self.peek = None;
+        }
+        if self.peek.is_none() {
+            self.peek = self.next_in(dir).map(|(msg, pos)| (dir, msg, pos));
+        }
+        self.peek.as_ref().map(|(_, msg, _)| msg.clone())
+    }
+
+    /// Consume the message next to the current position in `dir`.
+    fn take_in(&mut self, dir: Direction) -> Option<Gd<MCAPMessage>> {
+        self.peek_in(dir)?;
+        let (_, msg, pos) = self.peek.take()?;
+        self.gap = match dir {
+            Direction::Forward => Gap::After(pos),
+            Direction::Backward => Gap::Before(pos),
+        };
+        self.index += 1;
+        Some(msg)
+    }
+
+    /// Direction `for` loops run in.
+    fn iter_dir(&self) -> Direction {
+        match self.reverse {
+            true => Direction::Backward,
+            false => Direction::Forward,
+        }
+    }
+
+    /// Restart at the first message at or after `t_usec`; returns true if there is one.
+    fn seek_sources(&mut self, t_usec: u64) -> bool {
+        self.restart(Gap::Time(t_usec));
+        self.peek_in(Direction::Forward).is_some()
     }
 }
 
 #[godot_api]
 impl MCAPMessageIterator {
-    /// Filter to only a specific channel id
+    /// Order constant: by log time (default).
+    #[constant]
+    const ORDER_LOG_TIME: i64 = MCAPMessageOrder::LogTime as i64;
+    /// Order constant: by publish time.
+    #[constant]
+    const ORDER_PUBLISH_TIME: i64 = MCAPMessageOrder::PublishTime as i64;
+    /// Order constant: as stored in the file.
+    #[constant]
+    const ORDER_FILE: i64 = MCAPMessageOrder::File as i64;
+
+    /// Filter to only a specific channel id (replacing any other channel selection).
     #[func]
     pub fn for_channel(&mut self, channel_id: i32) {
-        self.filter_channel = Some(channel_id as u16);
+        self.selection = ChannelSelection::default();
+        self.selection.ids.insert(channel_id as u16);
         self.reset_iteration_state();
     }
 
-    /// Godot iterator protocol: initialize and prepare first value.
+    /// Add channel ids to the selection and rewind.
     #[func]
-    pub fn _iter_init(&mut self, _iter: Array<Variant>) -> bool {
+    pub fn for_channels(&mut self, channel_ids: PackedInt32Array) {
+        self.selection
+            .ids
+            .extend(channel_ids.as_slice().iter().map(|id| *id as u16));
+        self.reset_iteration_state();
+    }
+
+    /// Add topic names to the selection and rewind.
+    #[func]
+    pub fn for_topics(&mut self, topics: PackedStringArray) {
+        self.selection
+            .topics
+            .extend(topics.as_slice().iter().map(|t| t.to_string()));
+        self.reset_iteration_state();
+    }
+
+    /// Add a topic glob (`*` and `?` wildcards, as in `String.match()`) to the selection and rewind.
+    #[func]
+    pub fn for_topic_pattern(&mut self, pattern: GString) {
+        self.selection.globs.push(pattern);
+        self.reset_iteration_state();
+    }
+
+    /// Add a topic regular expression (`RegEx` syntax, matching anywhere in the topic) to the
+    /// selection and rewind. Returns false if the expression doesn't compile.
+    #[func]
+    pub fn for_topic_regex(&mut self, pattern: GString) -> bool {
+        let Some(re) = RegEx::create_from_string(&pattern).filter(|re| re.is_valid()) else {
+            godot_error!("MCAPMessageIterator: invalid topic regex '{}'", pattern);
+            return false;
+        };
+        self.selection.regexes.push(re);
+        self.reset_iteration_state();
+        true
+    }
+
+    /// Add schema names to the selection (channels using one of them) and rewind.
+    #[func]
+    pub fn for_schemas(&mut self, schema_names: PackedStringArray) {
+        self.selection
+            .schemas
+            .extend(schema_names.as_slice().iter().map(|t| t.to_string()));
+        self.reset_iteration_state();
+    }
+
+    /// Only yield messages with `start_usec <= log_time <= end_usec` and rewind; a negative bound
+    /// leaves that side open. Chunks outside the window are never loaded, so iteration ends at
+    /// the window's end.
+    #[func]
+    pub fn set_time_window(&mut self, start_usec: i64, end_usec: i64) {
+        self.window = (
+            (start_usec >= 0).then_some(start_usec as u64),
+            (end_usec >= 0).then_some(end_usec as u64),
+        );
+        self.reset_iteration_state();
+    }
+
+    /// Remove the time window and rewind.
+    #[func]
+    pub fn clear_time_window(&mut self) {
+        self.window = (None, None);
+        self.reset_iteration_state();
+    }
+
+    /// Only yield messages satisfying `predicate` and rewind; null removes it. Messages failing
+    /// it are skipped while walking the chunks, in both directions.
+    #[func]
+    pub fn set_predicate(&mut self, predicate: Option<Gd<MCAPMessagePredicate>>) {
+        self.predicate = predicate;
         self.reset_iteration_state();
-        self.peek = self.next_message_internal();
-        self.peek.is_some()
+    }
+
+    /// Shorthand for `set_predicate(MCAPMessagePredicate.from_callable(callable))`.
+    #[func]
+    pub fn set_predicate_callable(&mut self, callable: Callable) {
+        self.set_predicate(Some(MCAPMessagePredicate::from_callable(callable)));
+    }
+
+    /// The predicate set with `set_predicate()`, or null.
+    #[func]
+    pub fn get_predicate(&self) -> Option<Gd<MCAPMessagePredicate>> {
+        self.predicate.clone()
+    }
+
+    /// Set the order messages are yielded in and rewind the iterator.
+    #[func]
+    pub fn set_order(&mut self, order: MCAPMessageOrder) {
+        self.order = order;
+        self.reset_iteration_state();
+    }
+
+    /// Godot iterator protocol: initialize and prepare first value (the last one if `reverse`).
+    #[func]
+    pub fn _iter_init(&mut self, _iter: Array<Variant>) -> bool {
+        let dir = self.iter_dir();
+        self.restart(match dir {
+            Direction::Forward => Gap::Start,
+            Direction::Backward => Gap::End,
+        });
+        self.peek_in(dir).is_some()
     }
 
     /// Godot iterator protocol: advance and report if another value exists.
     #[func]
     pub fn _iter_next(&mut self, _iter: Array<Variant>) -> bool {
-        self.index += 1;
-        self.peek = self.next_message_internal();
-        self.peek.is_some()
+        let dir = self.iter_dir();
+        self.take_in(dir);
+        self.peek_in(dir).is_some()
     }
 
     /// Godot iterator protocol: return current value.
     #[func]
     pub fn _iter_get(&self, _value: Variant) -> Variant {
         match &self.peek {
-            Some(gd) => Variant::from(gd.clone()),
+            Some((_, gd, _)) => Variant::from(gd.clone()),
             None => Variant::nil(),
         }
     }
@@ -246,10 +1118,16 @@ impl MCAPMessageIterator {
         self.reset_iteration_state();
     }
 
-    /// Remove any channel filter and reset iteration.
+    /// Position after the last message, e.g. to step backwards with `get_prev_message()`.
+    #[func]
+    pub fn seek_to_end(&mut self) {
+        self.restart(Gap::End);
+    }
+
+    /// Remove the channel selection and reset iteration (the time window is kept).
     #[func]
     pub fn clear_filter(&mut self) {
-        self.filter_channel = None;
+        self.selection = ChannelSelection::default();
         self.reset_iteration_state();
     }
 
@@ -266,29 +1144,15 @@ impl MCAPMessageIterator {
         if !self.ensure_summary() {
             return false;
         }
-        let t: u64 = if log_time_usec < 0 {
-            0
-        } else {
-            log_time_usec as u64
-        };
-        let ci = {
-            let summary = match &self.summary {
-                Some(s) => s,
-                None => return false,
-            };
-            let mut found: Option<usize> = None;
-            for (i, ch) in summary.chunk_indexes.iter().enumerate() {
-                if ch.message_end_time >= t {
-                    found = Some(i);
-                    break;
-                }
-            }
-            match found {
-                Some(i) => i,
-                None => return false,
-            }
-        };
-        self.load_and_seek_at_or_after(ci, t)
+        let t_usec = log_time_usec.max(0) as u64;
+        if !self
+            .sources
+            .iter()
+            .any(|s| s.has_chunk_ending_at_or_after(t_usec))
+        {
+            return false;
+        }
+        self.seek_sources(t_usec)
     }
 
     /// Seek to the first message at or after time; if none exists, position to nearest at or before.
@@ -300,16 +1164,24 @@ impl MCAPMessageIterator {
         if !self.ensure_summary() {
             return false;
         }
-        let t: u64 = if log_time_usec < 0 {
-            0
-        } else {
-            log_time_usec as u64
-        };
-        let (ci, start_time) = match self.find_nearest_at_or_before(t) {
-            Some((i, time)) => (i, time),
-            None => return false,
-        };
-        self.load_and_seek_at_or_after(ci, start_time)
+        // Latest message at or before the time across sources (usec)
+        self.resolve_channels();
+        let mut best: Option<u64> = None;
+        for source in self.sources.iter() {
+            let unit = source.time_unit;
+            let t = unit.usec_to_unit_saturating(log_time_usec.max(0) as u64);
+            let filter = self.chunk_filter(unit);
+            if let Some(time) = source.find_nearest_at_or_before(t, &filter) {
+                let usec = unit.to_usec(time);
+                if best.map(|bu| usec > bu).unwrap_or(true) {
+                    best = Some(usec);
+                }
+            }
+        }
+        match best {
+            Some(usec) => self.seek_sources(usec),
+            None => false,
+        }
     }
 
     /// Seek to the first message on the given channel strictly after after_time_usec.
@@ -322,46 +1194,31 @@ impl MCAPMessageIterator {
             return false;
         }
         let ch_id = channel_id as u16;
-        let t: u64 = if after_time_usec < 0 {
-            0
-        } else {
-            after_time_usec as u64
-        };
         // Scan for earliest message strictly after t on the given channel
-        let summary = match &self.summary {
-            Some(s) => s,
-            None => return false,
-        };
-        let bytes = self.buf.as_slice();
-        let mut found: Option<(usize, u64)> = None;
-        let filter = MsgFilter {
-            time_start: Some(t.saturating_add(1)),
-            time_end: None,
-            channels: Some({
-                let mut s = HashSet::new();
-                s.insert(ch_id);
-                s
-            }),
-        };
-        for (i, chunk_idx) in summary.chunk_indexes.iter().enumerate() {
-            if chunk_idx.message_end_time <= t {
-                continue;
-            }
-            let mut best_in_chunk: Option<u64> = None;
-            let _ = stream_chunk_apply(bytes, summary, chunk_idx, &filter, |time, _gd| {
-                if best_in_chunk.map(|bt| time < bt).unwrap_or(true) {
-                    best_in_chunk = Some(time);
+        let mut found: Option<u64> = None;
+        for source in self.sources.iter() {
+            let unit = source.time_unit;
+            let t = unit.usec_to_unit_saturating(after_time_usec.max(0) as u64);
+            let filter = MsgFilter {
+                time_start: Some(t.saturating_add(1)),
+                time_end: None,
+                channels: Some({
+                    let mut s = HashSet::new();
+                    s.insert(ch_id);
+                    s
+                }),
+            };
+            if let Some(time) = source.find_first_after(t, &filter) {
+                let usec = unit.to_usec(time);
+                if found.map(|fu| usec < fu).unwrap_or(true) {
+                    found = Some(usec);
                 }
-            });
-            if let Some(start_time) = best_in_chunk {
-                found = Some((i, start_time));
-                break;
             }
         }
-        if let Some((ci, start_time)) = found {
-            return self.load_and_seek_at_or_after(ci, start_time);
+        match found {
+            Some(usec) => self.seek_sources(usec),
+            None => false,
         }
-        false
     }
 
     /// Get the message at an exact log time for a given channel, if present.
@@ -378,73 +1235,81 @@ impl MCAPMessageIterator {
             return None;
         }
         let ch_id = channel_id as u16;
-        let t: u64 = if log_time_usec < 0 {
-            0
-        } else {
-            log_time_usec as u64
-        };
-        let summary = self.summary.as_ref()?;
-        for chunk_idx in &summary.chunk_indexes {
-            if t < chunk_idx.message_start_time || t > chunk_idx.message_end_time {
-                continue;
-            }
-            match summary.read_message_indexes(self.buf.as_slice(), chunk_idx) {
-                Ok(map) => {
-                    for (ch, entries) in map.into_iter() {
-                        if ch.id != ch_id {
-                            continue;
-                        }
-                        if let Ok(pos) = entries.binary_search_by(|e| e.log_time.cmp(&t)) {
-                            let entry = &entries[pos];
-                            match summary.seek_message(self.buf.as_slice(), chunk_idx, entry) {
-                                Ok(msg) => return Some(MCAPMessage::from_mcap(&msg)),
-                                Err(e) => {
-                                    godot_error!("get_message_at_time: seek_message failed: {}", e);
-                                    return None;
-                                }
-                            }
-                        }
-                    }
-                }
-                Err(e) => {
-                    godot_error!("get_message_at_time: read_message_indexes failed: {}", e);
-                    return None;
+        let t_usec = log_time_usec.max(0) as u64;
+        self.sources
+            .iter()
+            .find_map(|source| source.message_at_time(ch_id, t_usec))
+            .filter(|msg| {
+                self.predicate
+                    .as_ref()
+                    .is_none_or(|p| p.bind().matches(msg.clone()))
+            })
+    }
+
+    /// The latest message at or before `log_time_usec` on each channel passing the channel
+    /// selection and time window, sorted by log time, e.g. to restore state after a seek. Found
+    /// through the message indexes; the predicate is not applied. Does not move the iterator.
+    #[func]
+    pub fn latest_messages_at(&mut self, log_time_usec: i64) -> Array<Gd<MCAPMessage>> {
+        if !self.ensure_summary() {
+            return Array::new();
+        }
+        self.resolve_channels();
+        let t_usec = log_time_usec.max(0) as u64;
+        // Latest per channel across sources; a channel can appear in several files
+        let mut latest: HashMap<u16, Gd<MCAPMessage>> = HashMap::new();
+        for source in self.sources.iter() {
+            let unit = source.time_unit;
+            let filter = self.chunk_filter(unit);
+            for msg in source.latest_per_channel(unit.usec_to_unit_saturating(t_usec), &filter) {
+                let ch = msg.bind().channel.bind().id;
+                let newer = latest
+                    .get(&ch)
+                    .is_none_or(|m| msg.bind().log_time >= m.bind().log_time);
+                if newer {
+                    latest.insert(ch, msg);
                 }
             }
         }
-        None
+        let mut msgs: Vec<Gd<MCAPMessage>> = latest.into_values().collect();
+        msgs.sort_by_key(|m| (m.bind().log_time, m.bind().channel.bind().id));
+        msgs.into_iter().collect()
     }
 
     /// Check if another message is available without consuming it.
     #[func]
     pub fn has_next_message(&mut self) -> bool {
-        if self.peek.is_none() {
-            self.peek = self.next_message_internal();
-        }
-        self.peek.is_some()
+        self.peek_in(Direction::Forward).is_some()
     }
 
     /// Fetch and advance to the next message; returns null if none.
     #[func]
     pub fn get_next_message(&mut self) -> Option<Gd<MCAPMessage>> {
-        if self.peek.is_none() {
-            self.peek = self.next_message_internal();
-        }
-        match self.peek.take() {
-            Some(gd) => {
-                self.index += 1;
-                Some(gd)
-            }
-            None => None,
-        }
+        self.take_in(Direction::Forward)
     }
 
     /// Return, without consuming, the next message if available.
     #[func]
     pub fn peek_message(&mut self) -> Option<Gd<MCAPMessage>> {
-        if self.peek.is_none() {
-            self.peek = self.next_message_internal();
-        }
-        self.peek.clone()
+        self.peek_in(Direction::Forward)
+    }
+
+    /// Check if a message precedes the current position.
+    #[func]
+    pub fn has_prev_message(&mut self) -> bool {
+        self.peek_in(Direction::Backward).is_some()
+    }
+
+    /// Step back: return the message before the current position and move before it; null if
+    /// none. Right after `get_next_message()`, this returns the same message again.
+    #[func]
+    pub fn get_prev_message(&mut self) -> Option<Gd<MCAPMessage>> {
+        self.take_in(Direction::Backward)
+    }
+
+    /// Return, without moving, the message before the current position if available.
+    #[func]
+    pub fn peek_prev_message(&mut self) -> Option<Gd<MCAPMessage>> {
+        self.peek_in(Direction::Backward)
     }
 }
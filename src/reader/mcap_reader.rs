@@ -1,20 +1,42 @@
 use crate::reader::MCAPMessageIterator;
-use crate::reader::buf::{BufBackend, SharedBuf};
-use crate::reader::filter::{MsgFilter, stream_chunk_apply};
+use crate::reader::buf::{BufBackend, FileBuf, ReaderBuf, SharedBuf};
+use crate::reader::chunks::{ChunkCache, DEFAULT_CHUNK_CACHE_BYTES};
+use crate::reader::columns::{self, FieldSpec};
+use crate::reader::filter::{ChunkSource, MsgFilter, stream_chunk_apply};
+use crate::reader::index::{SyntheticIndex, read_message_indexes};
+use crate::reader::predicate::MCAPMessagePredicate;
+use crate::reader::resources::{ResourceCache, SharedResources};
+use crate::recovery::copy_records;
 use crate::types::*;
-use godot::classes::ProjectSettings;
+use crate::writer::{MergedSummary, SinkTarget, SummaryOptions, WriterSink};
+use enumset::enum_set;
 use godot::classes::file_access::ModeFlags;
+use godot::classes::{DirAccess, ProjectSettings};
+use godot::global::{bytes_to_var, var_to_bytes};
 use godot::prelude::*;
 use godot::tools::GFile;
-use mcap::read::{
-    MessageStream, Options, RawMessage, RawMessageStream, Summary, footer as mcap_footer,
-};
+use mcap::read::{LinearReader, MessageStream, Options, RawMessage, RawMessageStream, Summary};
+use mcap::records::{AttachmentIndex, MetadataIndex, Record, op};
 use std::borrow::Cow;
+use std::cell::Cell;
 use std::collections::HashSet;
-use std::io::Read;
+use std::io::{Read, Write};
 use std::ops::ControlFlow;
+use std::rc::Rc;
 use std::sync::Arc;
 
+/// Size of a DataEnd record (opcode, length and data section CRC).
+const DATA_END_LEN: u64 = 1 + 8 + 4;
+
+/// Where `MCAPWriter.open_append()` continues an existing file, and what that file holds so far.
+pub(crate) struct AppendPoint {
+    pub summary: Summary,
+    /// End of the intact data section: the offset of its DataEnd record, if it has one.
+    pub data_end: u64,
+    /// Description of the damaged or incomplete tail that appending drops, if any.
+    pub damage: Option<String>,
+}
+
 #[derive(GodotClass)]
 /// MCAP file reader for Godot with sequential and indexed helpers.
 ///
@@ -22,19 +44,34 @@ use std::sync::Arc;
 /// - Loads an MCAP file into memory for fast random access and optional indexed queries.
 /// - Provides direct streaming of messages (`messages`, `raw_messages`) and an indexed iterator (`stream_messages_iterator`).
 /// - Exposes attachment and metadata access via summary indexes when present.
-/// - Identity note: every read constructs new Godot Resources (`MCAPMessage`, `MCAPChannel`, `MCAPSchema`, etc.).
-///   Do not rely on instance equality across calls; compare by stable fields instead (e.g. `channel.id`, `schema.id`).
+/// - Identity note: each channel and schema is one `MCAPChannel`/`MCAPSchema` Resource, created on first
+///   use and shared by all messages, iterators and replays of this reader, so `msg.channel == other.channel`
+///   works. Treat them as read-only. Messages, attachments and metadata are newly constructed per read.
 ///
 /// Memory & I/O
 /// - When opening from a path, the reader first tries to memory-map (mmap) the file for zero-copy random access.
-///   If mmap/OS open fails (e.g., due to platform or filesystem constraints), it falls back to loading into a PackedByteArray.
+///   If mmap/OS open fails (e.g. `res://` inside an exported PCK, Android, Web), it reads through `FileAccess`
+///   on demand instead: only the summary, footer and the chunks/attachments/metadata being accessed are read,
+///   with a small block cache, so indexed access keeps memory bounded even for multi-GB files.
 /// - When created from bytes via `from_bytes`, it stores the provided PackedByteArray (no mmap).
 /// - Direct read APIs (`messages`, `raw_messages`) iterate the data stream and do not require a Summary.
+///   Like `build_index()`, `refresh()` and `recover()`, they need the whole file at once and read it into
+///   memory when it isn't memory-mapped.
 /// - Indexed helpers (attachments, metadata, chunk/message indexes, and the iterator below) require a Summary section.
+/// - Decompressed chunks are kept in an LRU cache shared by the reader's indexed queries, its iterators and
+///   replays (`set_chunk_cache_size()`, 32 MiB by default), so each chunk is decoded once while it stays cached.
+///   Hit/miss/eviction counters help tune the size.
 ///
 /// Summary requirements
 /// - If the file has no summary, index-based methods return empty/0/false and set `last_error`.
 /// - Check with `has_summary()` or call `read_summary()` to obtain a Resource view.
+/// - For files without summary (written with summary/indexes disabled, or still being recorded),
+///   `build_index()` scans the data section once and synthesizes one; `save_index(path)` and
+///   `load_index(path)` keep it in a sidecar file so the scan isn't repeated.
+///
+/// Time units
+/// - All times are microseconds (usec). Set `time_unit` if the file stores another unit
+///   (e.g. nanoseconds for files from Foxglove/ROS 2 tooling); conversion happens transparently.
 ///
 /// Errors
 /// - On failure, methods set an internal error string retrievable with `get_last_error()`.
@@ -66,17 +103,26 @@ use std::sync::Arc;
 /// var attachments := reader.attachments()
 /// var meta := reader.metadata_entries()
 /// ```
-#[class(no_init)]
+#[class(no_init, base=RefCounted)]
 pub struct MCAPReader {
     path: GString,
-    /// Buffer for random access (mmap-backed or owned PackedByteArray).
+    /// Buffer for random access (mmap-backed, owned PackedByteArray, or FileAccess reads).
     pub(super) buf: SharedBuf,
-    /// Cached summary.
+    /// Cached summary (read from the file, or synthesized by `build_index()`/`load_index()`).
     pub(super) summary: Option<Summary>,
+    /// Index synthesized for files without summary; also provides their message indexes.
+    pub(super) synthetic: Option<Arc<SyntheticIndex>>,
+    /// Channel/schema Resources shared by all messages read through this reader.
+    pub(super) resources: SharedResources,
     /// If true, tolerate missing end-of-file magic.
     #[export]
     ignore_end_magic: bool,
+    /// Unit of the timestamps stored in the file. All times exposed by and passed to the reader
+    /// (and iterators/replays created afterwards) are microseconds regardless of this setting.
+    #[export]
+    pub(super) time_unit: MCAPTimeUnit,
     last_error: String,
+    base: Base<RefCounted>,
 }
 
 impl MCAPReader {
@@ -105,20 +151,33 @@ impl MCAPReader {
     }
 
     // Core walker over indexed messages using chunk streaming
-    fn for_each_indexed_msg<F>(&mut self, filter: &MsgFilter, mut visitor: F) -> Result<(), String>
+    fn for_each_indexed_msg<F>(
+        &mut self,
+        filter: &MsgFilter,
+        predicate: Option<&MCAPMessagePredicate>,
+        mut visitor: F,
+    ) -> Result<(), String>
     where
         F: FnMut(&Gd<MCAPMessage>) -> ControlFlow<()>,
     {
         // Clone the bytes handle first to avoid conflicting borrows with summary
         let bytes = self.buf.clone();
+        let resources = self.resources.clone();
+        let unit = self.time_unit;
         let s = self.with_summary()?;
+        let source = ChunkSource {
+            buf: &bytes,
+            summary: s,
+            resources: &resources,
+            unit,
+        };
         for chunk_idx in &s.chunk_indexes {
             if !filter.chunk_might_match(chunk_idx) {
                 continue;
             }
             // Stream and collect in a local vector to avoid borrowing self.buf across visitor calls
             let mut tmp: Vec<Gd<MCAPMessage>> = Vec::new();
-            stream_chunk_apply(bytes.as_slice(), s, chunk_idx, filter, |_, gd| tmp.push(gd))?;
+            stream_chunk_apply(&source, chunk_idx, filter, predicate, |_, gd| tmp.push(gd))?;
             for gd in tmp.iter() {
                 if let ControlFlow::Break(()) = visitor(gd) {
                     return Ok(());
@@ -134,12 +193,19 @@ impl MCAPReader {
     /// Open file and return a new reader instance.
     #[func]
     pub fn open(path: GString, ignore_end_magic: bool) -> Gd<Self> {
-        let mut reader = Gd::from_object(Self {
+        let mut reader = Gd::from_init_fn(|base| Self {
             path: path.clone(),
-            buf: Arc::new(BufBackend::Memory(PackedByteArray::new())),
+            buf: ReaderBuf::new(
+                BufBackend::Memory(PackedByteArray::new()),
+                Arc::new(ChunkCache::new(DEFAULT_CHUNK_CACHE_BYTES)),
+            ),
             summary: None,
+            synthetic: None,
+            resources: ResourceCache::new_shared(),
             ignore_end_magic,
+            time_unit: MCAPTimeUnit::Microseconds,
             last_error: String::new(),
+            base,
         });
         if !reader.bind_mut().load_from_path(path) {
             // keep error message; return object so caller can inspect get_last_error
@@ -150,23 +216,81 @@ impl MCAPReader {
     /// Create a reader from in-memory bytes.
     #[func]
     pub fn from_bytes(data: PackedByteArray, ignore_end_magic: bool) -> Gd<Self> {
-        let mut reader = Gd::from_object(Self {
+        let mut reader = Gd::from_init_fn(|base| Self {
             path: GString::from("<memory>"),
-            buf: Arc::new(BufBackend::Memory(data)),
+            buf: ReaderBuf::new(
+                BufBackend::Memory(data),
+                Arc::new(ChunkCache::new(DEFAULT_CHUNK_CACHE_BYTES)),
+            ),
             summary: None,
+            synthetic: None,
+            resources: ResourceCache::new_shared(),
             ignore_end_magic,
+            time_unit: MCAPTimeUnit::Microseconds,
             last_error: String::new(),
+            base,
         });
         // Preload summary (non-fatal if missing)
         let _ = reader.bind_mut().ensure_summary();
         reader
     }
 
+    /// Rebuild a truncated or unfinalized MCAP file, e.g. one left behind by a crash mid-recording.
+    ///
+    /// The data section is scanned without relying on the summary or footer; a partial or corrupt
+    /// tail is dropped and everything before it is written to `path_out` as a complete file with
+    /// chunk indexes, message indexes, statistics and attachment/metadata indexes. Schema and
+    /// channel IDs as well as timestamps are kept as-is.
+    ///
+    /// - Pass an empty `path_out` (or `path_in` itself) to repair the file in place; the result is
+    ///   written to `<path_in>.recover.tmp` first and then moved over the original.
+    /// - `options` controls chunking/compression of the output; when null, defaults are used and
+    ///   the profile/library of the original header are kept.
+    ///
+    /// Returns an `MCAPRecoveryReport` describing what was salvaged and lost (`ok` is false and
+    /// `error` is set if nothing could be written).
+    #[func]
+    pub fn recover(
+        path_in: GString,
+        path_out: GString,
+        options: Option<Gd<MCAPWriteOptions>>,
+    ) -> Gd<MCAPRecoveryReport> {
+        let mut report = MCAPRecoveryReport {
+            ok: false,
+            error: GString::new(),
+            was_finalized: false,
+            damage: GString::new(),
+            message_count: 0,
+            attachment_count: 0,
+            metadata_count: 0,
+            schema_count: 0,
+            channel_count: 0,
+            bytes_in: 0,
+            bytes_salvaged: 0,
+            bytes_lost: 0,
+            bytes_out: 0,
+        };
+        match Self::recover_into(&path_in, &path_out, options, &mut report) {
+            Ok(()) => report.ok = true,
+            Err(e) => {
+                godot_error!("recover({}) failed: {}", path_in, e);
+                report.error = GString::from(e.as_str());
+            }
+        }
+        Gd::from_object(report)
+    }
+
     /// Close and release buffers/caches.
     #[func]
     pub fn close(&mut self) {
-        self.buf = Arc::new(BufBackend::Memory(PackedByteArray::new()));
+        self.buf.chunks.clear();
+        self.resources.clear();
+        self.buf = ReaderBuf::new(
+            BufBackend::Memory(PackedByteArray::new()),
+            self.buf.chunks.clone(),
+        );
         self.summary = None;
+        self.synthetic = None;
         self.path = GString::new();
         self.clear_error();
     }
@@ -177,6 +301,203 @@ impl MCAPReader {
         self.summary.is_some()
     }
 
+    /// Scan the data section once and synthesize a summary for a file that has none, e.g. one
+    /// written without summary/chunk indexes or still being recorded.
+    ///
+    /// Afterwards `has_summary()` returns true and all indexed helpers, `stream_messages_iterator()`
+    /// and `MCAPReplay` work as usual. Messages written outside of chunks are counted but cannot be
+    /// reached through the index. Does nothing (and returns true) if the file has a summary with
+    /// chunk indexes; a summary without them is replaced.
+    #[func]
+    pub fn build_index(&mut self) -> bool {
+        if self.has_indexed_summary() {
+            return true;
+        }
+        let buf = self.buf.clone();
+        match buf
+            .contiguous()
+            .and_then(|bytes| SyntheticIndex::build(&bytes))
+        {
+            Ok(index) => {
+                if index.unchunked_messages > 0 {
+                    godot_warn!(
+                        "build_index: {} messages outside of chunks cannot be indexed",
+                        index.unchunked_messages
+                    );
+                }
+                self.install_index(index);
+                true
+            }
+            Err(e) => {
+                self.set_error(format!("build_index failed: {}", e));
+                false
+            }
+        }
+    }
+
+    /// Persist the index built by `build_index()` as a sidecar file for reuse with `load_index()`.
+    #[func]
+    pub fn save_index(&mut self, path: GString) -> bool {
+        let Some(index) = &self.synthetic else {
+            self.set_error("save_index: no synthesized index (call build_index() first)");
+            return false;
+        };
+        let bytes = var_to_bytes(&index.to_dictionary().to_variant());
+        let result = GFile::open(&path, ModeFlags::WRITE)
+            .map_err(|e| e.to_string())
+            .and_then(|mut f| f.write_all(bytes.as_slice()).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            self.set_error(format!("Failed to write {}: {}", path, e));
+            return false;
+        }
+        self.clear_error();
+        true
+    }
+
+    /// Load an index saved with `save_index()` instead of scanning the file again.
+    ///
+    /// Fails if the sidecar doesn't match the size of the opened file (e.g. because the recording
+    /// has grown since). Does nothing (and returns true) if the file has a summary with chunk indexes.
+    #[func]
+    pub fn load_index(&mut self, path: GString) -> bool {
+        if self.has_indexed_summary() {
+            return true;
+        }
+        let mut bytes = Vec::new();
+        let read = match GFile::open(&path, ModeFlags::READ) {
+            Ok(mut f) => f.read_to_end(&mut bytes).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = read {
+            self.set_error(format!("Failed to read {}: {}", path, e));
+            return false;
+        }
+        let index = bytes_to_var(&PackedByteArray::from(bytes))
+            .try_to::<Dictionary>()
+            .map_err(|e| e.to_string())
+            .and_then(|d| SyntheticIndex::from_dictionary(&d));
+        match index {
+            Ok(index) if index.source_len == self.buf.len() => {
+                self.install_index(index);
+                true
+            }
+            Ok(_) => {
+                self.set_error(format!(
+                    "load_index: {} was built for a different file",
+                    path
+                ));
+                false
+            }
+            Err(e) => {
+                self.set_error(format!("load_index: invalid index {}: {}", path, e));
+                false
+            }
+        }
+    }
+
+    /// Returns true if the summary was synthesized by `build_index()`/`load_index()`.
+    #[func]
+    pub fn is_index_synthetic(&self) -> bool {
+        self.synthetic.is_some()
+    }
+
+    /// Emitted by `refresh()` when data was appended to the file since it was last checked.
+    /// `file_size` is the new size in bytes, `new_messages` the number of newly indexed messages.
+    #[signal]
+    fn data_appended(file_size: i64, new_messages: i64);
+
+    /// Follow mode: pick up data appended to a file that is still being written.
+    ///
+    /// Re-checks the file length and, if it grew, reads only the part after the last indexed
+    /// record and indexes the newly completed records (chunks become visible once the writer
+    /// finishes them, e.g. on `MCAPWriter.flush()`). Records that are only partially written are
+    /// left for the next call. The first call on a file without summary indexes all of it, like
+    /// `build_index()`. Once the writer has closed the file, its real summary is used instead.
+    ///
+    /// Iterators with `follow` enabled and `MCAPReplay` with `follow` enabled continue with the new
+    /// messages. Call this periodically (e.g. from a Timer). Returns the number of newly indexed
+    /// messages and emits `data_appended` when the file grew.
+    #[func]
+    pub fn refresh(&mut self) -> i64 {
+        if self.has_indexed_summary() {
+            return 0;
+        }
+        if self.path.is_empty() || self.path == GString::from("<memory>") {
+            self.set_error("refresh: reader was not opened from a file");
+            return 0;
+        }
+        let old_len = self.buf.len();
+        if self.synthetic.is_some() {
+            // Cheap check first so polling an idle file doesn't map it again
+            let abs = ProjectSettings::singleton().globalize_path(&self.path);
+            if let Ok(meta) = std::fs::metadata(abs.to_string())
+                && meta.len() <= old_len
+            {
+                return 0;
+            }
+        }
+        let buf = match Self::load_buf(&self.path) {
+            Ok(buf) => buf,
+            Err(e) => {
+                self.set_error(format!("refresh failed: {}", e));
+                return 0;
+            }
+        };
+        let new_len = buf.len();
+        let grew = new_len > old_len;
+        if grew {
+            // Chunks already cached are unchanged in the grown file
+            self.buf = ReaderBuf::new(buf, self.buf.chunks.clone());
+        } else if self.synthetic.is_some() {
+            return 0;
+        }
+
+        // The writer may have finished the file in the meantime
+        if let Ok(Some(summary)) = self.buf.read_summary() {
+            let before = self.summary.as_ref().and_then(|s| s.stats.as_ref());
+            let before = before.map(|st| st.message_count).unwrap_or(0);
+            let after = summary.stats.as_ref().map(|st| st.message_count);
+            let new_messages = after.unwrap_or(0).saturating_sub(before) as i64;
+            self.summary = Some(summary);
+            self.synthetic = None;
+            self.clear_error();
+            if grew {
+                self.signals()
+                    .data_appended()
+                    .emit(new_len as i64, new_messages);
+            }
+            return new_messages;
+        }
+
+        // Only the part after the last indexed record is read (a first scan reads everything)
+        let buf = self.buf.clone();
+        let mut index = match &self.synthetic {
+            Some(index) => SyntheticIndex::clone(index),
+            None => SyntheticIndex::new(),
+        };
+        let from = index.scanned_len.min(new_len);
+        let result = buf
+            .read_at(from, new_len - from)
+            .and_then(|tail| index.extend(&tail))
+            .map(|n| (index, n));
+        let new_messages = match result {
+            Ok((index, n)) => {
+                self.install_index(index);
+                n as i64
+            }
+            Err(e) => {
+                self.set_error(format!("refresh failed: {}", e));
+                return 0;
+            }
+        };
+        if grew {
+            self.signals()
+                .data_appended()
+                .emit(new_len as i64, new_messages);
+        }
+        new_messages
+    }
+
     /// Read or return cached summary as a Godot resource.
     #[func]
     pub fn read_summary(&mut self) -> Option<Gd<MCAPSummary>> {
@@ -189,7 +510,7 @@ impl MCAPReader {
     /// Read and return the footer.
     #[func]
     pub fn footer(&mut self) -> Option<Gd<MCAPFooter>> {
-        match mcap_footer(self.buf.as_slice()) {
+        match self.buf.footer() {
             Ok(f) => Some(self.footer_to_resource(&f)),
             Err(e) => {
                 self.set_error(format!("footer() failed: {}", e));
@@ -204,6 +525,57 @@ impl MCAPReader {
         GString::from(self.last_error.as_str())
     }
 
+    // ----- Chunk cache -----
+
+    /// Limit (in decompressed bytes) of the cache of decoded chunks shared by this reader's indexed
+    /// queries, iterators and replays. Least recently used chunks are evicted; 0 disables caching.
+    #[func]
+    pub fn set_chunk_cache_size(&mut self, max_bytes: i64) {
+        self.buf.chunks.set_max_bytes(max_bytes.max(0) as u64);
+    }
+
+    /// Current chunk cache limit in bytes (32 MiB by default).
+    #[func]
+    pub fn get_chunk_cache_size(&self) -> i64 {
+        self.buf.chunks.max_bytes() as i64
+    }
+
+    /// Bytes currently held by the chunk cache.
+    #[func]
+    pub fn get_chunk_cache_bytes(&self) -> i64 {
+        self.buf.chunks.stats().bytes as i64
+    }
+
+    /// Number of chunks currently held by the chunk cache.
+    #[func]
+    pub fn get_chunk_cache_entry_count(&self) -> i64 {
+        self.buf.chunks.stats().entries as i64
+    }
+
+    /// Number of chunk lookups served from the cache.
+    #[func]
+    pub fn get_chunk_cache_hit_count(&self) -> i64 {
+        self.buf.chunks.stats().hits as i64
+    }
+
+    /// Number of chunk lookups that had to decompress the chunk.
+    #[func]
+    pub fn get_chunk_cache_miss_count(&self) -> i64 {
+        self.buf.chunks.stats().misses as i64
+    }
+
+    /// Number of chunks evicted to stay within the size limit.
+    #[func]
+    pub fn get_chunk_cache_eviction_count(&self) -> i64 {
+        self.buf.chunks.stats().evictions as i64
+    }
+
+    /// Drop all cached chunks and reset the hit/miss/eviction counters.
+    #[func]
+    pub fn clear_chunk_cache(&mut self) {
+        self.buf.chunks.clear();
+    }
+
     /// Reads all messages as Godot `MCAPMessage` resources (allocates payloads as needed).
     /// Stops automatically before the summary section.
     #[func]
@@ -211,7 +583,15 @@ impl MCAPReader {
         let mut out: Array<Gd<MCAPMessage>> = Array::new();
         self.clear_error();
         let opts = self.opts_enumset();
-        let stream = match MessageStream::new_with_options(self.buf.as_slice(), opts) {
+        let buf = self.buf.clone();
+        let bytes = match buf.contiguous() {
+            Ok(b) => b,
+            Err(e) => {
+                self.set_error(format!("Reading file failed: {}", e));
+                return out;
+            }
+        };
+        let stream = match MessageStream::new_with_options(&bytes, opts) {
             Ok(s) => s,
             Err(e) => {
                 self.set_error(format!("Creating MessageStream failed: {}", e));
@@ -222,7 +602,7 @@ impl MCAPReader {
         for item in stream {
             match item {
                 Ok(msg) => {
-                    let gd = MCAPMessage::from_mcap(&msg);
+                    let gd = self.resources.message(&msg, self.time_unit);
                     out.push(&gd);
                 }
                 Err(e) => {
@@ -238,10 +618,11 @@ impl MCAPReader {
     ///
     /// Details
     /// - Requires a Summary section (uses chunk/message indexes for efficient seeking).
-    /// - For files without a summary, this iterator will be empty; use `messages()` instead.
+    /// - For files without a summary, this iterator will be empty; use `messages()` instead, or call
+    ///   `build_index()` (or `refresh()` for files still being written) first.
     #[func]
     pub fn stream_messages_iterator(&self) -> Gd<MCAPMessageIterator> {
-        MCAPMessageIterator::new_from_reader(self, None)
+        MCAPMessageIterator::new_from_reader(self)
     }
 
     /// Reads all raw messages (header + bytes) without constructing channels into Godot resources.
@@ -251,7 +632,15 @@ impl MCAPReader {
         let mut out: Array<Dictionary> = Array::new();
         self.clear_error();
         let opts = self.opts_enumset();
-        let stream = match RawMessageStream::new_with_options(self.buf.as_slice(), opts) {
+        let buf = self.buf.clone();
+        let bytes = match buf.contiguous() {
+            Ok(b) => b,
+            Err(e) => {
+                self.set_error(format!("Reading file failed: {}", e));
+                return out;
+            }
+        };
+        let stream = match RawMessageStream::new_with_options(&bytes, opts) {
             Ok(s) => s,
             Err(e) => {
                 self.set_error(format!("Creating RawMessageStream failed: {}", e));
@@ -262,7 +651,7 @@ impl MCAPReader {
         for item in stream {
             match item {
                 Ok(RawMessage { header, data }) => {
-                    let header_gd = MCAPMessageHeader::from_mcap(&header);
+                    let header_gd = MCAPMessageHeader::from_mcap(&header, self.time_unit);
                     let mut dict = Dictionary::new();
                     let _ = dict.insert("header", header_gd);
                     match data {
@@ -295,11 +684,18 @@ impl MCAPReader {
             return out;
         };
         for idx in &summary.attachment_indexes {
-            match mcap::read::attachment(self.buf.as_slice(), idx) {
-                Ok(att) => {
-                    let gd = MCAPAttachment::from_mcap(&att);
-                    out.push(&gd);
-                }
+            // Read just the record and look it up at offset 0
+            let local = AttachmentIndex {
+                offset: 0,
+                ..idx.clone()
+            };
+            let att = self.buf.read_at(idx.offset, idx.length).and_then(|bytes| {
+                mcap::read::attachment(&bytes, &local)
+                    .map(|att| MCAPAttachment::from_mcap(&att, self.time_unit))
+                    .map_err(|e| e.to_string())
+            });
+            match att {
+                Ok(gd) => out.push(&gd),
                 Err(e) => {
                     self.set_error(format!("Reading attachment failed: {}", e));
                     break;
@@ -319,11 +715,17 @@ impl MCAPReader {
             return out;
         };
         for idx in &summary.metadata_indexes {
-            match mcap::read::metadata(self.buf.as_slice(), idx) {
-                Ok(meta) => {
-                    let gd = MCAPMetadata::from_mcap(&meta);
-                    out.push(&gd);
-                }
+            let local = MetadataIndex {
+                offset: 0,
+                ..idx.clone()
+            };
+            let meta = self.buf.read_at(idx.offset, idx.length).and_then(|bytes| {
+                mcap::read::metadata(&bytes, &local)
+                    .map(|meta| MCAPMetadata::from_mcap(&meta))
+                    .map_err(|e| e.to_string())
+            });
+            match meta {
+                Ok(gd) => out.push(&gd),
                 Err(e) => {
                     self.set_error(format!("Reading metadata failed: {}", e));
                     break;
@@ -370,10 +772,10 @@ impl MCAPReader {
         };
 
         let idx_native = self.chunk_index_from_resource(&idx);
-        match summary.read_message_indexes(self.buf.as_slice(), &idx_native) {
+        match read_message_indexes(summary, self.synthetic.as_deref(), &self.buf, &idx_native) {
             Ok(map) => {
                 for (ch, entries) in map.into_iter() {
-                    let ch_gd = MCAPChannel::from_mcap(ch.as_ref());
+                    let ch_gd = self.resources.channel(&ch);
                     let mut arr: Array<Gd<MCAPMessageIndexEntry>> = Array::new();
                     for e in entries.iter() {
                         arr.push(&self.message_index_entry_to_resource(ch.id, e));
@@ -403,8 +805,14 @@ impl MCAPReader {
         };
         let idx_native = self.chunk_index_from_resource(&idx);
         let entry_native = self.message_index_entry_from_resource(&entry);
-        match summary.seek_message(self.buf.as_slice(), &idx_native, &entry_native) {
-            Ok(msg) => Some(MCAPMessage::from_mcap(&msg)),
+        let msg = self.buf.chunk(&idx_native).and_then(|(bytes, local)| {
+            summary
+                .seek_message(&bytes, &local, &entry_native)
+                .map(|msg| self.resources.message(&msg, self.time_unit))
+                .map_err(|e| e.to_string())
+        });
+        match msg {
+            Ok(msg) => Some(msg),
             Err(e) => {
                 self.set_error(format!("seek_message failed: {}", e));
                 None
@@ -427,18 +835,13 @@ impl MCAPReader {
         if start_usec > end_usec {
             return out;
         }
-        let start = if start_usec < 0 {
-            0u64
-        } else {
-            start_usec as u64
-        };
-        let end = if end_usec < 0 { 0u64 } else { end_usec as u64 };
+        let (start, end) = self.file_time_range(start_usec, end_usec);
         let filter = MsgFilter {
             time_start: Some(start),
             time_end: Some(end),
             channels: None,
         };
-        if let Err(e) = self.for_each_indexed_msg(&filter, |gd| {
+        if let Err(e) = self.for_each_indexed_msg(&filter, None, |gd| {
             out.push(gd);
             ControlFlow::Continue(())
         }) {
@@ -462,7 +865,7 @@ impl MCAPReader {
             time_end: None,
             channels: Some(set),
         };
-        if let Err(e) = self.for_each_indexed_msg(&filter, |gd| {
+        if let Err(e) = self.for_each_indexed_msg(&filter, None, |gd| {
             out.push(gd);
             ControlFlow::Continue(())
         }) {
@@ -519,7 +922,32 @@ impl MCAPReader {
             time_end: None,
             channels: Some(set),
         };
-        if let Err(e) = self.for_each_indexed_msg(&filter, |gd| {
+        if let Err(e) = self.for_each_indexed_msg(&filter, None, |gd| {
+            out.push(gd);
+            ControlFlow::Continue(())
+        }) {
+            self.set_error(e);
+        }
+        out
+    }
+
+    /// Read messages satisfying `predicate` (see `MCAPMessagePredicate`), in log-time order, using
+    /// indexes. Negative `start_usec`/`end_usec` leave that side of the log-time range open and an
+    /// empty `channel_ids` reads all channels. Messages failing the predicate are dropped while the
+    /// chunks are walked; a Callable predicate must not use this reader.
+    #[func]
+    pub fn messages_where(
+        &mut self,
+        predicate: Gd<MCAPMessagePredicate>,
+        start_usec: i64,
+        end_usec: i64,
+        channel_ids: PackedInt32Array,
+    ) -> Array<Gd<MCAPMessage>> {
+        let mut out: Array<Gd<MCAPMessage>> = Array::new();
+        self.clear_error();
+        let filter = self.query_filter(&channel_ids, start_usec, end_usec);
+        let predicate = predicate.bind();
+        if let Err(e) = self.for_each_indexed_msg(&filter, Some(&*predicate), |gd| {
             out.push(gd);
             ControlFlow::Continue(())
         }) {
@@ -528,6 +956,53 @@ impl MCAPReader {
         out
     }
 
+    // ----- Columnar extraction -----
+
+    /// Read the messages on `channel_ids` (empty: all channels) with log time in
+    /// [start_usec, end_usec] (negative: unbounded) as columns, without building a Resource per
+    /// message: channel ids, log/publish times, sequences and, if `include_payload` is true, all
+    /// payloads in one `PackedByteArray` with `payload_offsets`. Rows are in log-time order.
+    /// On error, returns empty columns and sets last-error.
+    #[func]
+    pub fn extract_columns(
+        &mut self,
+        channel_ids: PackedInt32Array,
+        start_usec: i64,
+        end_usec: i64,
+        include_payload: bool,
+    ) -> Gd<MCAPColumns> {
+        self.extract(&channel_ids, start_usec, end_usec, include_payload, &[])
+    }
+
+    /// Like `extract_columns()` without payloads, but decodes fields at fixed offsets of every
+    /// payload into typed arrays in `MCAPColumns.fields`.
+    ///
+    /// `layout` is an Array of `{ "name": String, "type": String, "offset": int }` Dictionaries.
+    /// Types use the `BinaryStream` names and little-endian encoding: `u8`, `u16`, `u32`, `u64`,
+    /// `i8`, `i16`, `i32`, `i64` (→ `PackedInt64Array`), `half`, `f32` (→ `PackedFloat32Array`),
+    /// `f64` (→ `PackedFloat64Array`), `bool` (→ `PackedByteArray`), `vector2`, `vector3`
+    /// (→ `PackedVector2Array`/`PackedVector3Array`), `vector4`, `quaternion` (→ `PackedVector4Array`)
+    /// and `color` (→ `PackedColorArray`). Without `offset`, a field follows the previous one.
+    /// Returns null and sets last-error if the layout is invalid.
+    #[func]
+    pub fn extract_fields(
+        &mut self,
+        channel_ids: PackedInt32Array,
+        start_usec: i64,
+        end_usec: i64,
+        layout: Array<Dictionary>,
+    ) -> Option<Gd<MCAPColumns>> {
+        self.clear_error();
+        let fields = match columns::parse_layout(&layout) {
+            Ok(fields) => fields,
+            Err(e) => {
+                self.set_error(format!("extract_fields: {}", e));
+                return None;
+            }
+        };
+        Some(self.extract(&channel_ids, start_usec, end_usec, false, &fields))
+    }
+
     // ----- Basic file info -----
 
     /// First message log time in microseconds, or -1 if unavailable.
@@ -540,7 +1015,7 @@ impl MCAPReader {
             Some(s) => s
                 .stats
                 .as_ref()
-                .map(|st| st.message_start_time as i64)
+                .map(|st| self.time_unit.to_usec(st.message_start_time) as i64)
                 .unwrap_or(-1),
             None => -1,
         }
@@ -556,7 +1031,7 @@ impl MCAPReader {
             Some(s) => s
                 .stats
                 .as_ref()
-                .map(|st| st.message_end_time as i64)
+                .map(|st| self.time_unit.to_usec(st.message_end_time) as i64)
                 .unwrap_or(-1),
             None => -1,
         }
@@ -571,7 +1046,9 @@ impl MCAPReader {
         match &self.summary {
             Some(s) => {
                 if let Some(st) = &s.stats {
-                    (st.message_end_time as i64) - (st.message_start_time as i64)
+                    let unit = self.time_unit;
+                    (unit.to_usec(st.message_end_time) as i64)
+                        - (unit.to_usec(st.message_start_time) as i64)
                 } else {
                     -1
                 }
@@ -671,7 +1148,7 @@ impl MCAPReader {
             None => return None,
         };
         match &ch.schema {
-            Some(schema_arc) => Some(MCAPSchema::from_mcap(schema_arc.as_ref())),
+            Some(schema_arc) => Some(self.resources.schema(schema_arc)),
             None => None,
         }
     }
@@ -692,7 +1169,7 @@ impl MCAPReader {
         }
         let mut total: i64 = 0;
         for chunk_idx in &s.chunk_indexes {
-            match s.read_message_indexes(self.buf.as_slice(), chunk_idx) {
+            match read_message_indexes(s, self.synthetic.as_deref(), &self.buf, chunk_idx) {
                 Ok(map) => {
                     for (_ch, entries) in map.into_iter() {
                         total += entries.len() as i64;
@@ -726,7 +1203,7 @@ impl MCAPReader {
         };
         let mut total: i64 = 0;
         for chunk_idx in &s.chunk_indexes {
-            match s.read_message_indexes(self.buf.as_slice(), chunk_idx) {
+            match read_message_indexes(s, self.synthetic.as_deref(), &self.buf, chunk_idx) {
                 Ok(map) => {
                     for (ch, entries) in map.into_iter() {
                         if ch.id == ch_id {
@@ -758,18 +1235,13 @@ impl MCAPReader {
         if end_usec < start_usec {
             return 0;
         }
-        let start = if start_usec < 0 {
-            0u64
-        } else {
-            start_usec as u64
-        };
-        let end = if end_usec < 0 { 0u64 } else { end_usec as u64 };
+        let (start, end) = self.file_time_range(start_usec, end_usec);
         let mut total: i64 = 0;
         for chunk_idx in &s.chunk_indexes {
             if chunk_idx.message_start_time > end || chunk_idx.message_end_time < start {
                 continue;
             }
-            match s.read_message_indexes(self.buf.as_slice(), chunk_idx) {
+            match read_message_indexes(s, self.synthetic.as_deref(), &self.buf, chunk_idx) {
                 Ok(map) => {
                     for (_ch, entries) in map.into_iter() {
                         if entries.is_empty() {
@@ -822,18 +1294,13 @@ impl MCAPReader {
         } else {
             channel_id as u16
         };
-        let start = if start_usec < 0 {
-            0u64
-        } else {
-            start_usec as u64
-        };
-        let end = if end_usec < 0 { 0u64 } else { end_usec as u64 };
+        let (start, end) = self.file_time_range(start_usec, end_usec);
         let mut total: i64 = 0;
         for chunk_idx in &s.chunk_indexes {
             if chunk_idx.message_start_time > end || chunk_idx.message_end_time < start {
                 continue;
             }
-            match s.read_message_indexes(self.buf.as_slice(), chunk_idx) {
+            match read_message_indexes(s, self.synthetic.as_deref(), &self.buf, chunk_idx) {
                 Ok(map) => {
                     for (ch, entries) in map.into_iter() {
                         if ch.id != ch_id {
@@ -871,18 +1338,75 @@ impl MCAPReader {
 // ----- internal helpers -----
 impl MCAPReader {
     fn load_from_path(&mut self, path: GString) -> bool {
+        match Self::load_buf(&path) {
+            Ok(buf) => {
+                self.buf.chunks.clear();
+                self.resources.clear();
+                self.buf = ReaderBuf::new(buf, self.buf.chunks.clone());
+                let _ = self.ensure_summary();
+                true
+            }
+            Err(e) => {
+                self.set_error(e);
+                false
+            }
+        }
+    }
+
+    /// Locate the end of the data section of `path` and summarize what it contains.
+    ///
+    /// Only the footer and summary of a finalized file are read. Files without a summary (or
+    /// without statistics) and files that were never closed get a recovery scan of their data
+    /// section instead, which stops at the first damaged or incomplete record.
+    pub(crate) fn append_point(path: &GString) -> Result<AppendPoint, String> {
+        let buf = Self::load_buf(path)?;
+        if let Ok(footer) = buf.footer()
+            && footer.summary_start >= DATA_END_LEN
+            && let Ok(Some(summary)) = buf.read_summary()
+            && summary.stats.is_some()
+        {
+            let data_end = footer.summary_start - DATA_END_LEN;
+            if buf.read_at(data_end, 1)?[0] != op::DATA_END {
+                return Err("no DataEnd record in front of the summary".into());
+            }
+            return Ok(AppendPoint {
+                summary,
+                data_end,
+                damage: None,
+            });
+        }
+
+        let contents = buf.contiguous()?;
+        let index = SyntheticIndex::build(&contents)?;
+        let data_end = index.scanned_len;
+        if data_end == 0 {
+            return Err("the file has no intact header record".into());
+        }
+        let damage = match contents.get(data_end as usize) {
+            None | Some(&op::DATA_END) => None,
+            Some(_) => Some(format!(
+                "{} bytes after offset {} are damaged or incomplete",
+                contents.len() as u64 - data_end,
+                data_end
+            )),
+        };
+        Ok(AppendPoint {
+            summary: index.summary,
+            data_end,
+            damage,
+        })
+    }
+
+    /// Map `path` into memory, or read it on demand through FileAccess if mapping is not possible.
+    fn load_buf(path: &GString) -> Result<BufBackend, String> {
         // Try memory-mapping the file via an absolute OS path.
-        // Works for res:// and user:// by globalizing the path; fall back to GFile streaming copy if needed.
-        let abs = ProjectSettings::singleton().globalize_path(&path);
+        // Works for res:// and user:// by globalizing the path; fall back to positioned FileAccess reads if needed.
+        let abs = ProjectSettings::singleton().globalize_path(path);
         match std::fs::File::open(abs.to_string()) {
             Ok(file) => match unsafe { memmap2::MmapOptions::new().map(&file) } {
-                Ok(mmap) => {
-                    self.buf = Arc::new(BufBackend::Mmap(mmap));
-                    let _ = self.ensure_summary();
-                    return true;
-                }
+                Ok(mmap) => return Ok(BufBackend::Mmap(mmap)),
                 Err(e) => {
-                    godot_warn!("mmap failed, falling back to buffered read: {}", e);
+                    godot_warn!("mmap failed, falling back to FileAccess reads: {}", e);
                 }
             },
             Err(e) => {
@@ -890,28 +1414,113 @@ impl MCAPReader {
             }
         }
 
-        let mut file = match GFile::open(&path, ModeFlags::READ) {
-            Ok(f) => f,
-            Err(e) => {
-                self.set_error(format!("Failed to open {}: {}", path, e));
-                return false;
+        Ok(BufBackend::File(FileBuf::open(path)?))
+    }
+
+    fn recover_into(
+        path_in: &GString,
+        path_out: &GString,
+        options: Option<Gd<MCAPWriteOptions>>,
+        report: &mut MCAPRecoveryReport,
+    ) -> Result<(), String> {
+        let buf = Self::load_buf(path_in)?;
+        let contents = buf.contiguous()?;
+        let bytes: &[u8] = &contents;
+
+        let is_default = options.is_none();
+        let options = options.unwrap_or_else(MCAPWriteOptions::new_gd);
+        let mut opts = options.bind().to_mcap_owned();
+        if is_default {
+            let first = LinearReader::new_with_options(bytes, enum_set!(Options::IgnoreEndMagic))
+                .ok()
+                .and_then(|mut r| r.next());
+            if let Some(Ok(Record::Header(h))) = first {
+                opts = opts.profile(h.profile).library(h.library);
             }
+        }
+
+        let sink = WriterSink::in_memory(Rc::new(Cell::new(0)));
+        let mut merged = MergedSummary::new(
+            Summary::default(),
+            SummaryOptions::new(&options.bind()),
+            &sink,
+        );
+        let mut w = MergedSummary::writer_options(opts)
+            .create(sink)
+            .map_err(|e| e.to_string())?;
+        let scan = copy_records(bytes, &mut w, &mut merged)?;
+        if scan.valid_len == 0 {
+            return Err(scan.error.unwrap_or_else(|| "not an MCAP file".into()));
+        }
+        let SinkTarget::Memory(cursor) = merged.finish(w)?.into_target() else {
+            unreachable!("recovery writes to memory");
         };
-        let mut bytes = Vec::new();
-        if let Err(e) = file.read_to_end(&mut bytes) {
-            self.set_error(format!("Failed to read {}: {}", path, e));
-            return false;
+        let out = cursor.into_inner();
+
+        report.was_finalized = scan.reached_data_end;
+        report.damage = GString::from(scan.error.as_deref().unwrap_or(""));
+        report.message_count = scan.messages as i64;
+        report.attachment_count = scan.attachments as i64;
+        report.metadata_count = scan.metadata as i64;
+        report.schema_count = scan.schemas.len() as i64;
+        report.channel_count = scan.channels.len() as i64;
+        report.bytes_in = bytes.len() as i64;
+        report.bytes_salvaged = scan.valid_len as i64;
+        report.bytes_lost = if scan.error.is_some() {
+            (bytes.len() - scan.valid_len) as i64
+        } else {
+            0
+        };
+        report.bytes_out = out.len() as i64;
+        // Release the input (it may be memory-mapped) before touching the file system
+        drop(scan);
+        drop(contents);
+        drop(buf);
+
+        let in_place = path_out.is_empty() || path_out == path_in;
+        let target = if in_place { path_in } else { path_out };
+        let write_path = if in_place {
+            GString::from(format!("{}.recover.tmp", path_in).as_str())
+        } else {
+            path_out.clone()
+        };
+        let mut file = GFile::open(&write_path, ModeFlags::WRITE)
+            .map_err(|e| format!("Failed to open {}: {}", write_path, e))?;
+        file.write_all(&out)
+            .map_err(|e| format!("Failed to write {}: {}", write_path, e))?;
+        drop(file);
+        if in_place {
+            let err = DirAccess::rename_absolute(&write_path, target);
+            if err != godot::global::Error::OK {
+                return Err(format!(
+                    "Failed to replace {} with {}: {:?}",
+                    target, write_path, err
+                ));
+            }
         }
-        self.buf = Arc::new(BufBackend::Memory(PackedByteArray::from(bytes)));
-        let _ = self.ensure_summary();
-        true
+        Ok(())
+    }
+
+    /// True if the file has its own summary with chunk indexes (so there's nothing to build).
+    fn has_indexed_summary(&self) -> bool {
+        self.synthetic.is_none()
+            && self
+                .summary
+                .as_ref()
+                .is_some_and(|s| !s.chunk_indexes.is_empty())
+    }
+
+    fn install_index(&mut self, index: SyntheticIndex) {
+        self.summary = Some(index.summary.clone());
+        self.synthetic = Some(Arc::new(index));
+        self.clear_error();
     }
 
     fn ensure_summary(&mut self) -> Result<(), String> {
         if self.summary.is_some() {
             return Ok(());
         }
-        match Summary::read(self.buf.as_slice()) {
+        match self.buf.read_summary() {
             Ok(opt) => {
                 self.summary = opt;
                 Ok(())
@@ -924,6 +1533,61 @@ impl MCAPReader {
         }
     }
 
+    /// Filter for the query helpers taking optional bounds: negative times leave that side open,
+    /// an empty `channel_ids` selects all channels. IDs that can't be channel IDs (negative or
+    /// above 65535) match nothing.
+    fn query_filter(
+        &self,
+        channel_ids: &PackedInt32Array,
+        start_usec: i64,
+        end_usec: i64,
+    ) -> MsgFilter {
+        let unit = self.time_unit;
+        let channels: HashSet<u16> = channel_ids
+            .as_slice()
+            .iter()
+            .filter_map(|id| u16::try_from(*id).ok())
+            .collect();
+        MsgFilter {
+            time_start: (start_usec >= 0).then(|| unit.usec_to_unit_ceil(start_usec as u64)),
+            time_end: (end_usec >= 0).then(|| unit.usec_to_unit_saturating(end_usec as u64)),
+            channels: (!channel_ids.is_empty()).then_some(channels),
+        }
+    }
+
+    fn extract(
+        &mut self,
+        channel_ids: &PackedInt32Array,
+        start_usec: i64,
+        end_usec: i64,
+        include_payload: bool,
+        layout: &[FieldSpec],
+    ) -> Gd<MCAPColumns> {
+        self.clear_error();
+        let filter = self.query_filter(channel_ids, start_usec, end_usec);
+        let buf = self.buf.clone();
+        let unit = self.time_unit;
+        let result = self
+            .with_summary()
+            .and_then(|s| columns::extract(&buf, s, &filter, unit, include_payload, layout));
+        match result {
+            Ok(columns) => columns,
+            Err(e) => {
+                self.set_error(format!("extract_columns: {}", e));
+                columns::empty()
+            }
+        }
+    }
+
+    /// Convert an inclusive [start_usec, end_usec] query into file time units (negative = 0).
+    pub(super) fn file_time_range(&self, start_usec: i64, end_usec: i64) -> (u64, u64) {
+        let unit = self.time_unit;
+        (
+            unit.usec_to_unit_ceil(start_usec.max(0) as u64),
+            unit.usec_to_unit_saturating(end_usec.max(0) as u64),
+        )
+    }
+
     fn opts_enumset(&self) -> enumset::EnumSet<Options> {
         let mut set = enumset::EnumSet::empty();
         if self.ignore_end_magic {
@@ -946,8 +1610,8 @@ impl MCAPReader {
             let _ = dict.insert(*k as i32, *v as i64);
         }
         Gd::from_object(MCAPChunkIndex {
-            message_start_time: idx.message_start_time as i64,
-            message_end_time: idx.message_end_time as i64,
+            message_start_time: self.time_unit.to_usec(idx.message_start_time) as i64,
+            message_end_time: self.time_unit.to_usec(idx.message_end_time) as i64,
             chunk_start_offset: idx.chunk_start_offset as i64,
             chunk_length: idx.chunk_length as i64,
             message_index_offsets: dict,
@@ -967,8 +1631,12 @@ impl MCAPReader {
             map.insert(key as u16, val as u64);
         }
         mcap::records::ChunkIndex {
-            message_start_time: b.message_start_time as u64,
-            message_end_time: b.message_end_time as u64,
+            message_start_time: self
+                .time_unit
+                .usec_to_unit_saturating(b.message_start_time.max(0) as u64),
+            message_end_time: self
+                .time_unit
+                .usec_to_unit_saturating(b.message_end_time.max(0) as u64),
             chunk_start_offset: b.chunk_start_offset as u64,
             chunk_length: b.chunk_length as u64,
             message_index_offsets: map,
@@ -986,7 +1654,7 @@ impl MCAPReader {
     ) -> Gd<MCAPMessageIndexEntry> {
         Gd::from_object(MCAPMessageIndexEntry {
             channel_id: channel_id as i32,
-            log_time_usec: e.log_time as i64,
+            log_time_usec: self.time_unit.to_usec(e.log_time) as i64,
             offset_uncompressed: e.offset as i64,
         })
     }
@@ -997,7 +1665,9 @@ impl MCAPReader {
     ) -> mcap::records::MessageIndexEntry {
         let b = e.bind();
         mcap::records::MessageIndexEntry {
-            log_time: b.log_time_usec as u64,
+            log_time: self
+                .time_unit
+                .usec_to_unit_saturating(b.log_time_usec.max(0) as u64),
             offset: b.offset_uncompressed as u64,
         }
     }
@@ -1012,19 +1682,21 @@ impl MCAPReader {
             let _ = stats.insert("attachment_count", st.attachment_count as i64);
             let _ = stats.insert("metadata_count", st.metadata_count as i64);
             let _ = stats.insert("chunk_count", st.chunk_count as i64);
-            let _ = stats.insert("message_start_time", st.message_start_time as i64);
-            let _ = stats.insert("message_end_time", st.message_end_time as i64);
+            let start = self.time_unit.to_usec(st.message_start_time) as i64;
+            let end = self.time_unit.to_usec(st.message_end_time) as i64;
+            let _ = stats.insert("message_start_time", start);
+            let _ = stats.insert("message_end_time", end);
         }
 
         // channels/schemas
         let mut channels = Dictionary::new();
         for (id, ch) in s.channels.iter() {
-            let gd = MCAPChannel::from_mcap(ch.as_ref());
+            let gd = self.resources.channel(ch);
             let _ = channels.insert(*id as i32, gd);
         }
         let mut schemas = Dictionary::new();
         for (id, sc) in s.schemas.iter() {
-            let gd = MCAPSchema::from_mcap(sc.as_ref());
+            let gd = self.resources.schema(sc);
             let _ = schemas.insert(*id as i32, gd);
         }
 
@@ -1039,8 +1711,8 @@ impl MCAPReader {
             att_arr.push(&Gd::from_object(MCAPAttachmentIndex {
                 offset: a.offset as i64,
                 length: a.length as i64,
-                log_time: a.log_time as i64,
-                create_time: a.create_time as i64,
+                log_time: self.time_unit.to_usec(a.log_time) as i64,
+                create_time: self.time_unit.to_usec(a.create_time) as i64,
                 data_size: a.data_size as i64,
                 name: GString::from(a.name.as_str()),
                 media_type: GString::from(a.media_type.as_str()),
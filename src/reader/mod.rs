@@ -1,11 +1,21 @@
 mod buf;
+mod chunks;
+mod columns;
 mod filter;
+mod index;
 mod iterator;
 mod mcap_reader;
+mod multi_reader;
+mod predicate;
 mod replay;
+mod resources;
 
 pub use iterator::MCAPMessageIterator;
 #[allow(unused_imports)]
 pub use mcap_reader::MCAPReader;
 #[allow(unused_imports)]
-pub use replay::{MCAPReplay, ProcessingMode};
+pub use multi_reader::MCAPMultiReader;
+#[allow(unused_imports)]
+pub use predicate::MCAPMessagePredicate;
+#[allow(unused_imports)]
+pub use replay::{MCAPReplay, ProcessingMode, ReplayClockMode};
@@ -0,0 +1,532 @@
+use crate::reader::MCAPMessageIterator;
+use crate::reader::mcap_reader::MCAPReader;
+use crate::reader::predicate::MCAPMessagePredicate;
+use crate::reader::resources::{ResourceCache, SharedResources};
+use crate::types::*;
+use godot::prelude::*;
+use mcap::read::Summary;
+use mcap::{Channel, Schema};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashSet};
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Channels and schemas of one file in a merged view, keyed by the IDs used in that file.
+/// Shared with the view's iterators; `MCAPMultiReader.refresh()` adds channels that appear later.
+#[derive(Default)]
+pub(super) struct IdMap {
+    pub schemas: RefCell<BTreeMap<u16, Arc<Schema<'static>>>>,
+    pub channels: RefCell<BTreeMap<u16, Arc<Channel<'static>>>>,
+}
+
+impl IdMap {
+    /// `summary` with its channels and schemas replaced by the merged ones, so messages and message
+    /// indexes read through it carry merged IDs.
+    pub fn apply(&self, summary: &Summary) -> Summary {
+        let mut out = summary.clone();
+        for (id, schema) in self.schemas.borrow().iter() {
+            out.schemas.insert(*id, schema.clone());
+        }
+        for (id, channel) in self.channels.borrow().iter() {
+            out.channels.insert(*id, channel.clone());
+        }
+        out
+    }
+}
+
+/// `preferred` if it is still free, else the lowest free ID (schema ID 0 means "no schema").
+fn free_id<T>(taken: &BTreeMap<u16, T>, preferred: u16, allow_zero: bool) -> u16 {
+    if (allow_zero || preferred != 0) && !taken.contains_key(&preferred) {
+        return preferred;
+    }
+    (0..=u16::MAX)
+        .find(|id| (allow_zero || *id != 0) && !taken.contains_key(id))
+        .unwrap_or(preferred)
+}
+
+#[derive(GodotClass)]
+/// Read-only view over several MCAP files as one recording.
+///
+/// Overview
+/// - Opens a set of files (e.g. per-robot recordings or the segments of an `MCAPRotatingWriter`)
+///   and merges their channels and schemas into one ID space.
+/// - `stream_messages_iterator()` returns an `MCAPMessageIterator` that yields the messages of all
+///   files in log-time order; `MCAPReplay.set_multi_reader()` plays the session back as one.
+/// - Counts and time bounds cover all files.
+///
+/// Merged IDs
+/// - Channels with the same topic, message encoding, metadata and schema are merged into one
+///   channel, even if their IDs differ between files. Schemas are merged by name, encoding and data.
+/// - A merged channel or schema keeps the ID it has in the first file that uses it when that ID is
+///   still free; otherwise it gets the lowest free ID. Use `merged_channel_id()` to translate.
+/// - Messages, channels and schemas returned by this view and its iterators carry merged IDs.
+///
+/// Summary requirements
+/// - Like `MCAPReader`, indexed access requires a Summary in each file. Files without one are left
+///   out until `build_index()` is called.
+/// - All times are microseconds (usec). Set `time_unit` on the individual readers (see `get_reader()`)
+///   if files store other units, before creating iterators.
+///
+/// Basic usage (GDScript)
+/// ```gdscript
+/// var session := MCAPMultiReader.open(["user://run_000.mcap", "user://run_001.mcap"], false)
+/// print(session.topic_names(), " ", session.duration_usec())
+/// for msg in session.stream_messages_iterator():
+///     print(msg.channel.topic, " @ ", msg.log_time)
+/// ```
+#[class(no_init, base=RefCounted)]
+pub struct MCAPMultiReader {
+    readers: Vec<Gd<MCAPReader>>,
+    /// Per-reader file IDs → merged channels/schemas.
+    maps: Vec<Rc<IdMap>>,
+    schemas: BTreeMap<u16, Arc<Schema<'static>>>,
+    channels: BTreeMap<u16, Arc<Channel<'static>>>,
+    /// Resources of the merged channels/schemas, shared by the session's messages.
+    resources: SharedResources,
+    last_error: String,
+    base: Base<RefCounted>,
+}
+
+impl MCAPMultiReader {
+    fn new_gd(readers: Vec<Gd<MCAPReader>>) -> Gd<Self> {
+        let mut view = Gd::from_init_fn(|base| Self {
+            readers,
+            maps: Vec::new(),
+            schemas: BTreeMap::new(),
+            channels: BTreeMap::new(),
+            resources: ResourceCache::new_shared(),
+            last_error: String::new(),
+            base,
+        });
+        view.bind_mut().remap();
+        view
+    }
+
+    /// Merge the schemas and channels of one file's summary that `map` does not cover yet into the
+    /// view, and add them to `map`.
+    fn merge_summary(&mut self, map: &IdMap, summary: &Summary) {
+        // A merged ID is used at most once per file, so channels of one file are never folded together
+        let mut used_schemas: HashSet<u16> = map.schemas.borrow().values().map(|s| s.id).collect();
+        let mut used_channels: HashSet<u16> =
+            map.channels.borrow().values().map(|c| c.id).collect();
+
+        let mut schemas: Vec<_> = summary
+            .schemas
+            .values()
+            .filter(|s| !map.schemas.borrow().contains_key(&s.id))
+            .collect();
+        schemas.sort_by_key(|s| s.id);
+        for schema in schemas {
+            let merged = self.merge_schema(schema, &mut used_schemas);
+            map.schemas.borrow_mut().insert(schema.id, merged);
+        }
+
+        let mut channels: Vec<_> = summary
+            .channels
+            .values()
+            .filter(|c| !map.channels.borrow().contains_key(&c.id))
+            .collect();
+        channels.sort_by_key(|c| c.id);
+        for channel in channels {
+            let schema = channel.schema.as_ref().map(|s| {
+                let known = map.schemas.borrow().get(&s.id).cloned();
+                match known {
+                    Some(merged) => merged,
+                    None => {
+                        let merged = self.merge_schema(s, &mut used_schemas);
+                        map.schemas.borrow_mut().insert(s.id, merged.clone());
+                        merged
+                    }
+                }
+            });
+            let existing = self.channels.values().find(|c| {
+                !used_channels.contains(&c.id)
+                    && c.topic == channel.topic
+                    && c.message_encoding == channel.message_encoding
+                    && c.metadata == channel.metadata
+                    && c.schema.as_ref().map(|s| s.id) == schema.as_ref().map(|s| s.id)
+            });
+            let merged = match existing {
+                Some(c) => c.clone(),
+                None => {
+                    let id = free_id(&self.channels, channel.id, true);
+                    let merged = Arc::new(Channel {
+                        id,
+                        topic: channel.topic.clone(),
+                        schema,
+                        message_encoding: channel.message_encoding.clone(),
+                        metadata: channel.metadata.clone(),
+                    });
+                    self.channels.insert(id, merged.clone());
+                    merged
+                }
+            };
+            used_channels.insert(merged.id);
+            map.channels.borrow_mut().insert(channel.id, merged);
+        }
+    }
+
+    fn merge_schema(
+        &mut self,
+        schema: &Schema<'static>,
+        used: &mut HashSet<u16>,
+    ) -> Arc<Schema<'static>> {
+        let existing = self.schemas.values().find(|s| {
+            !used.contains(&s.id)
+                && s.name == schema.name
+                && s.encoding == schema.encoding
+                && s.data == schema.data
+        });
+        let merged = match existing {
+            Some(s) => s.clone(),
+            None => {
+                let id = free_id(&self.schemas, schema.id, false);
+                let merged = Arc::new(Schema {
+                    id,
+                    ..schema.clone()
+                });
+                self.schemas.insert(id, merged.clone());
+                merged
+            }
+        };
+        used.insert(merged.id);
+        merged
+    }
+
+    /// File channel IDs of reader `i` that map to merged channel `channel_id`.
+    fn file_channel_ids(&self, i: usize, channel_id: u16) -> Vec<u16> {
+        self.maps[i]
+            .channels
+            .borrow()
+            .iter()
+            .filter(|(_, c)| c.id == channel_id)
+            .map(|(id, _)| *id)
+            .collect()
+    }
+}
+
+#[godot_api]
+impl MCAPMultiReader {
+    /// Open every file in `paths` and return a merged view. Files that fail to open are kept (so
+    /// indices match `paths`) but contribute nothing; see `get_last_error()`.
+    #[func]
+    pub fn open(paths: PackedStringArray, ignore_end_magic: bool) -> Gd<Self> {
+        let mut errors = Vec::new();
+        let readers: Vec<Gd<MCAPReader>> = paths
+            .as_slice()
+            .iter()
+            .map(|path| {
+                let reader = MCAPReader::open(path.clone(), ignore_end_magic);
+                let err = reader.bind().get_last_error();
+                if !err.is_empty() {
+                    errors.push(format!("{}: {}", path, err));
+                }
+                reader
+            })
+            .collect();
+        let mut view = Self::new_gd(readers);
+        if !errors.is_empty() {
+            view.bind_mut().last_error = errors.join("; ");
+        }
+        view
+    }
+
+    /// Create a merged view over already opened readers (e.g. with `time_unit` or indexes set up).
+    #[func]
+    pub fn from_readers(readers: Array<Gd<MCAPReader>>) -> Gd<Self> {
+        Self::new_gd(readers.iter_shared().collect())
+    }
+
+    /// Recompute the merged channel/schema IDs, e.g. after building a reader's index directly.
+    /// Iterators created before keep the IDs they were created with.
+    #[func]
+    pub fn remap(&mut self) {
+        self.schemas.clear();
+        self.channels.clear();
+        self.resources.clear();
+        let mut maps = Vec::with_capacity(self.readers.len());
+        for reader in self.readers.clone() {
+            let map = IdMap::default();
+            if let Some(summary) = &reader.bind().summary {
+                self.merge_summary(&map, summary);
+            }
+            maps.push(Rc::new(map));
+        }
+        self.maps = maps;
+    }
+
+    /// Synthesize an index (see `MCAPReader.build_index()`) for every file without a usable
+    /// summary, then update the merged IDs. Returns false if any file failed.
+    #[func]
+    pub fn build_index(&mut self) -> bool {
+        let mut ok = true;
+        let mut errors = Vec::new();
+        for (i, reader) in self.readers.iter_mut().enumerate() {
+            let mut r = reader.bind_mut();
+            if !r.build_index() {
+                ok = false;
+                errors.push(format!("file {}: {}", i, r.get_last_error()));
+            }
+        }
+        self.last_error = errors.join("; ");
+        self.remap();
+        ok
+    }
+
+    /// Follow mode: call `MCAPReader.refresh()` on every file and return the total number of newly
+    /// indexed messages. Channels and schemas that first appear get merged IDs like on `remap()`,
+    /// also for iterators created before. Refresh the files through this view, not their readers.
+    #[func]
+    pub fn refresh(&mut self) -> i64 {
+        let mut added = 0;
+        for (reader, map) in self.readers.clone().iter_mut().zip(self.maps.clone()) {
+            added += reader.bind_mut().refresh();
+            if let Some(summary) = &reader.bind().summary {
+                self.merge_summary(&map, summary);
+            }
+        }
+        added
+    }
+
+    /// Number of files in the view.
+    #[func]
+    pub fn get_reader_count(&self) -> i32 {
+        self.readers.len() as i32
+    }
+
+    /// The reader of the file at `index` (in the order passed to `open()`/`from_readers()`).
+    #[func]
+    pub fn get_reader(&self, index: i32) -> Option<Gd<MCAPReader>> {
+        usize::try_from(index)
+            .ok()
+            .and_then(|i| self.readers.get(i).cloned())
+    }
+
+    /// Merged ID of channel `channel_id` of the file at `reader_index`, or -1 if unknown.
+    #[func]
+    pub fn merged_channel_id(&self, reader_index: i32, channel_id: i32) -> i32 {
+        let Some(map) = usize::try_from(reader_index)
+            .ok()
+            .and_then(|i| self.maps.get(i))
+        else {
+            return -1;
+        };
+        u16::try_from(channel_id)
+            .ok()
+            .and_then(|id| map.channels.borrow().get(&id).cloned())
+            .map_or(-1, |c| c.id as i32)
+    }
+
+    /// Returns true if every file has a summary.
+    #[func]
+    pub fn has_summary(&self) -> bool {
+        self.readers.iter().all(|r| r.bind().has_summary())
+    }
+
+    /// Returns the last encountered error message, or empty string if none.
+    #[func]
+    pub fn get_last_error(&self) -> GString {
+        GString::from(self.last_error.as_str())
+    }
+
+    /// Iterator over the messages of all files in log-time order (see `MCAPMessageIterator`).
+    #[func]
+    pub fn stream_messages_iterator(&self) -> Gd<MCAPMessageIterator> {
+        let sources: Vec<_> = self
+            .readers
+            .iter()
+            .zip(&self.maps)
+            .map(|(r, map)| (r.clone(), Some(map.clone())))
+            .collect();
+        MCAPMessageIterator::new_merged(sources, self.resources.clone())
+    }
+
+    /// Read messages of all files within [start_usec, end_usec] inclusive, in log-time order.
+    #[func]
+    pub fn messages_in_time_range(
+        &mut self,
+        start_usec: i64,
+        end_usec: i64,
+    ) -> Array<Gd<MCAPMessage>> {
+        let mut out: Array<Gd<MCAPMessage>> = Array::new();
+        if start_usec > end_usec {
+            return out;
+        }
+        let mut it = self.stream_messages_iterator();
+        let mut it = it.bind_mut();
+        if !it.seek_to_time(start_usec) {
+            return out;
+        }
+        while let Some(msg) = it.get_next_message() {
+            if msg.bind().log_time > end_usec {
+                break;
+            }
+            out.push(&msg);
+        }
+        out
+    }
+
+    /// Read messages of all files satisfying `predicate`, in log-time order. Negative
+    /// `start_usec`/`end_usec` leave that side open and an empty `channel_ids` (merged IDs) reads
+    /// all channels. A Callable predicate must not use this session.
+    #[func]
+    pub fn messages_where(
+        &mut self,
+        predicate: Gd<MCAPMessagePredicate>,
+        start_usec: i64,
+        end_usec: i64,
+        channel_ids: PackedInt32Array,
+    ) -> Array<Gd<MCAPMessage>> {
+        let mut out: Array<Gd<MCAPMessage>> = Array::new();
+        let mut it = self.stream_messages_iterator();
+        let mut it = it.bind_mut();
+        if !channel_ids.is_empty() {
+            it.for_channels(channel_ids);
+        }
+        it.set_time_window(start_usec, end_usec);
+        it.set_predicate(Some(predicate));
+        while let Some(msg) = it.get_next_message() {
+            out.push(&msg);
+        }
+        out
+    }
+
+    // ----- Merged file info -----
+
+    /// Earliest first message log time across files in microseconds, or -1 if unavailable.
+    #[func]
+    pub fn first_message_time_usec(&mut self) -> i64 {
+        self.readers
+            .iter_mut()
+            .map(|r| r.bind_mut().first_message_time_usec())
+            .filter(|t| *t >= 0)
+            .min()
+            .unwrap_or(-1)
+    }
+
+    /// Latest last message log time across files in microseconds, or -1 if unavailable.
+    #[func]
+    pub fn last_message_time_usec(&mut self) -> i64 {
+        self.readers
+            .iter_mut()
+            .map(|r| r.bind_mut().last_message_time_usec())
+            .max()
+            .unwrap_or(-1)
+    }
+
+    /// Duration of the whole session (last - first) in microseconds, or -1 if unavailable.
+    #[func]
+    pub fn duration_usec(&mut self) -> i64 {
+        let first = self.first_message_time_usec();
+        let last = self.last_message_time_usec();
+        if first < 0 || last < 0 {
+            return -1;
+        }
+        last - first
+    }
+
+    /// All merged channel IDs, sorted.
+    #[func]
+    pub fn channel_ids(&self) -> PackedInt32Array {
+        self.channels.keys().map(|id| *id as i32).collect()
+    }
+
+    /// Topic names of all merged channels (may contain duplicates across channels with different schemas).
+    #[func]
+    pub fn topic_names(&self) -> PackedStringArray {
+        let mut arr = PackedStringArray::new();
+        for ch in self.channels.values() {
+            arr.push(&GString::from(ch.topic.as_str()));
+        }
+        arr
+    }
+
+    /// Resolve a topic name to the lowest matching merged channel id, or -1 if not found.
+    #[func]
+    pub fn topic_to_channel_id(&self, topic: GString) -> i32 {
+        let t = topic.to_string();
+        self.channels
+            .values()
+            .find(|ch| ch.topic == t)
+            .map_or(-1, |ch| ch.id as i32)
+    }
+
+    /// Merged channel with the given id, if any.
+    #[func]
+    pub fn get_channel(&self, channel_id: i32) -> Option<Gd<MCAPChannel>> {
+        let id = u16::try_from(channel_id).ok()?;
+        self.channels.get(&id).map(|ch| self.resources.channel(ch))
+    }
+
+    /// All merged channel IDs that reference a given merged schema id.
+    #[func]
+    pub fn channels_for_schema(&self, schema_id: i32) -> PackedInt32Array {
+        self.channels
+            .values()
+            .filter(|ch| ch.schema.as_ref().is_some_and(|s| s.id as i32 == schema_id))
+            .map(|ch| ch.id as i32)
+            .collect()
+    }
+
+    /// Return the schema object used by a merged channel, if any.
+    #[func]
+    pub fn schema_for_channel(&self, channel_id: i32) -> Option<Gd<MCAPSchema>> {
+        let id = u16::try_from(channel_id).ok()?;
+        let schema = self.channels.get(&id)?.schema.as_ref()?;
+        Some(self.resources.schema(schema))
+    }
+
+    // ----- Counts -----
+
+    /// Total message count across files.
+    #[func]
+    pub fn message_count_total(&mut self) -> i64 {
+        self.readers
+            .iter_mut()
+            .map(|r| r.bind_mut().message_count_total())
+            .sum()
+    }
+
+    /// Message count for a merged channel id across files.
+    #[func]
+    pub fn message_count_for_channel(&mut self, channel_id: i32) -> i64 {
+        self.message_count_for_channel_in_range(channel_id, -1, -1)
+    }
+
+    /// Message count across all files within [start_usec, end_usec] inclusive.
+    #[func]
+    pub fn message_count_in_range(&mut self, start_usec: i64, end_usec: i64) -> i64 {
+        self.readers
+            .iter_mut()
+            .map(|r| r.bind_mut().message_count_in_range(start_usec, end_usec))
+            .sum()
+    }
+
+    /// Message count for a merged channel within [start_usec, end_usec] inclusive. Pass -1 for
+    /// both bounds to count the whole session.
+    #[func]
+    pub fn message_count_for_channel_in_range(
+        &mut self,
+        channel_id: i32,
+        start_usec: i64,
+        end_usec: i64,
+    ) -> i64 {
+        let Ok(id) = u16::try_from(channel_id) else {
+            return 0;
+        };
+        let whole = start_usec < 0 && end_usec < 0;
+        let mut total = 0;
+        for i in 0..self.readers.len() {
+            let mut reader = self.readers[i].clone();
+            for file_id in self.file_channel_ids(i, id) {
+                let mut r = reader.bind_mut();
+                total += if whole {
+                    r.message_count_for_channel(file_id as i32)
+                } else {
+                    r.message_count_for_channel_in_range(file_id as i32, start_usec, end_usec)
+                };
+            }
+        }
+        total
+    }
+}
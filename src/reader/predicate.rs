@@ -0,0 +1,229 @@
+use crate::types::*;
+use godot::classes::Json;
+use godot::global::Error;
+use godot::prelude::*;
+
+/// Condition on message contents, used to drop messages while iterating or querying.
+///
+/// Overview
+/// - `from_callable()` wraps a Callable that receives the `MCAPMessage` and returns true to keep it.
+/// - `json_field()` (and `json_field_equals()`) compare a field of JSON-encoded payloads natively,
+///   before any `MCAPMessage` is built. Messages on channels whose `message_encoding` isn't `json`,
+///   payloads that don't parse and payloads without the field never match (except for `OP_NOT_EXISTS`).
+/// - Field paths are dot separated; numeric segments index into arrays (e.g. `"targets.0.id"`).
+///   An empty path refers to the whole payload.
+/// - Comparisons follow GDScript operator semantics, so the JSON number `5.0` equals `5`.
+///
+/// Usage (GDScript)
+/// ```gdscript
+/// var errors := MCAPMessagePredicate.json_field(
+///     "level", MCAPMessagePredicate.OP_GREATER_EQUAL, 40)
+/// it.set_predicate(errors)
+///
+/// var entity := MCAPMessagePredicate.from_callable(
+///     func(msg: MCAPMessage): return msg.data.decode_u32(0) == 7)
+/// var hits := reader.messages_where(entity, -1, -1, PackedInt32Array())
+/// ```
+#[derive(GodotClass)]
+#[class(no_init, base=RefCounted)]
+pub struct MCAPMessagePredicate {
+    kind: PredicateKind,
+}
+
+enum PredicateKind {
+    Callable(Callable),
+    JsonField {
+        path: Vec<String>,
+        op: FieldOp,
+        value: Variant,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FieldOp {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Contains,
+    Exists,
+    NotExists,
+}
+
+impl FieldOp {
+    fn from_i64(op: i64) -> Option<Self> {
+        Some(match op {
+            0 => Self::Equal,
+            1 => Self::NotEqual,
+            2 => Self::Less,
+            3 => Self::LessEqual,
+            4 => Self::Greater,
+            5 => Self::GreaterEqual,
+            6 => Self::Contains,
+            7 => Self::Exists,
+            8 => Self::NotExists,
+            _ => return None,
+        })
+    }
+
+    fn apply(self, field: Option<&Variant>, value: &Variant) -> bool {
+        let op = match self {
+            Self::Exists => return field.is_some(),
+            Self::NotExists => return field.is_none(),
+            Self::Equal => VariantOperator::EQUAL,
+            Self::NotEqual => VariantOperator::NOT_EQUAL,
+            Self::Less => VariantOperator::LESS,
+            Self::LessEqual => VariantOperator::LESS_EQUAL,
+            Self::Greater => VariantOperator::GREATER,
+            Self::GreaterEqual => VariantOperator::GREATER_EQUAL,
+            Self::Contains => {
+                // `value in field`: substring for strings, element for arrays, key for objects
+                return field
+                    .and_then(|f| value.evaluate(f, VariantOperator::IN))
+                    .is_some_and(|r| r.booleanize());
+            }
+        };
+        field
+            .and_then(|f| f.evaluate(value, op))
+            .is_some_and(|r| r.booleanize())
+    }
+}
+
+/// Look up a dot-separated path in parsed JSON.
+fn json_lookup(root: Variant, path: &[String]) -> Option<Variant> {
+    let mut cur = root;
+    for seg in path {
+        cur = match cur.get_type() {
+            VariantType::DICTIONARY => cur.to::<Dictionary>().get(seg.as_str())?,
+            VariantType::ARRAY => {
+                let i = seg.parse::<usize>().ok()?;
+                cur.to::<VariantArray>().get(i)?
+            }
+            _ => return None,
+        };
+    }
+    Some(cur)
+}
+
+impl MCAPMessagePredicate {
+    /// Check the payload without building a message. Callable predicates pass here and are
+    /// decided by `matches_message()`.
+    pub(crate) fn matches_raw(&self, message_encoding: &str, data: &[u8]) -> bool {
+        match &self.kind {
+            PredicateKind::Callable(_) => true,
+            PredicateKind::JsonField { path, op, value } => {
+                let field = if message_encoding.eq_ignore_ascii_case("json") {
+                    std::str::from_utf8(data).ok().and_then(|text| {
+                        let mut json = Json::new_gd();
+                        (json.parse(text) == Error::OK)
+                            .then(|| json_lookup(json.get_data(), path))
+                            .flatten()
+                    })
+                } else {
+                    None
+                };
+                op.apply(field.as_ref(), value)
+            }
+        }
+    }
+
+    /// Check a built message. Native predicates were already decided by `matches_raw()`.
+    pub(crate) fn matches_message(&self, msg: &Gd<MCAPMessage>) -> bool {
+        match &self.kind {
+            PredicateKind::Callable(callable) => callable.call(&[msg.to_variant()]).booleanize(),
+            PredicateKind::JsonField { .. } => true,
+        }
+    }
+
+    /// Run both checks, building the message with `build` only if the payload passes.
+    pub(crate) fn filter<F>(
+        &self,
+        message_encoding: &str,
+        data: &[u8],
+        build: F,
+    ) -> Option<Gd<MCAPMessage>>
+    where
+        F: FnOnce() -> Option<Gd<MCAPMessage>>,
+    {
+        if !self.matches_raw(message_encoding, data) {
+            return None;
+        }
+        build().filter(|msg| self.matches_message(msg))
+    }
+}
+
+#[godot_api]
+impl MCAPMessagePredicate {
+    /// Field operator: field == value.
+    #[constant]
+    const OP_EQUAL: i64 = 0;
+    /// Field operator: field != value.
+    #[constant]
+    const OP_NOT_EQUAL: i64 = 1;
+    /// Field operator: field < value.
+    #[constant]
+    const OP_LESS: i64 = 2;
+    /// Field operator: field <= value.
+    #[constant]
+    const OP_LESS_EQUAL: i64 = 3;
+    /// Field operator: field > value.
+    #[constant]
+    const OP_GREATER: i64 = 4;
+    /// Field operator: field >= value.
+    #[constant]
+    const OP_GREATER_EQUAL: i64 = 5;
+    /// Field operator: value in field (substring, array element or object key).
+    #[constant]
+    const OP_CONTAINS: i64 = 6;
+    /// Field operator: the field is present (value is ignored).
+    #[constant]
+    const OP_EXISTS: i64 = 7;
+    /// Field operator: the field is missing (value is ignored).
+    #[constant]
+    const OP_NOT_EXISTS: i64 = 8;
+
+    /// Predicate calling `callable(message)`; a truthy result keeps the message.
+    #[func]
+    pub fn from_callable(callable: Callable) -> Gd<Self> {
+        Gd::from_object(Self {
+            kind: PredicateKind::Callable(callable),
+        })
+    }
+
+    /// Predicate comparing the JSON field at `path` with `value` using one of the `OP_*` constants.
+    /// Returns null for an unknown operator.
+    #[func]
+    pub fn json_field(path: GString, op: i64, value: Variant) -> Option<Gd<Self>> {
+        let Some(op) = FieldOp::from_i64(op) else {
+            godot_error!("MCAPMessagePredicate: unknown operator {}", op);
+            return None;
+        };
+        let path = path.to_string();
+        let path = match path.is_empty() {
+            true => Vec::new(),
+            false => path.split('.').map(str::to_owned).collect(),
+        };
+        Some(Gd::from_object(Self {
+            kind: PredicateKind::JsonField { path, op, value },
+        }))
+    }
+
+    /// Shorthand for `json_field(path, OP_EQUAL, value)`.
+    #[func]
+    pub fn json_field_equals(path: GString, value: Variant) -> Option<Gd<Self>> {
+        Self::json_field(path, Self::OP_EQUAL, value)
+    }
+
+    /// Whether `message` satisfies the predicate.
+    #[func]
+    pub fn matches(&self, message: Gd<MCAPMessage>) -> bool {
+        let (encoding, data) = {
+            let m = message.bind();
+            let encoding = m.channel.bind().message_encoding.to_string();
+            (encoding, m.data.clone())
+        };
+        self.matches_raw(&encoding, data.as_slice()) && self.matches_message(&message)
+    }
+}
@@ -1,5 +1,6 @@
 use crate::reader::iterator::MCAPMessageIterator;
 use crate::reader::mcap_reader::MCAPReader;
+use crate::reader::multi_reader::MCAPMultiReader;
 use crate::types::*;
 use godot::classes::notify::NodeNotification;
 use godot::prelude::*;
@@ -19,23 +20,228 @@ pub enum ProcessingMode {
     PHYSICS,
 }
 
+#[derive(GodotConvert, Var, Export, PartialEq, Debug)]
+#[godot(via = i64)]
+/// Time source used by [MCAPReplay] to advance the replay time.
+///
+/// - WALL_CLOCK: real time, independent of the engine.
+/// - ENGINE_DELTA: the `delta` of the process/physics tick, so `Engine.time_scale` and tree pausing apply.
+/// - MANUAL: only `advance()` and `advance_to()` move the replay time.
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+pub enum ReplayClockMode {
+    /// Advance with real (wall-clock) time (default).
+    WALL_CLOCK,
+    /// Advance by the accumulated process/physics `delta`.
+    ENGINE_DELTA,
+    /// Advance only through `advance()`/`advance_to()`.
+    MANUAL,
+}
+
+/// Result of taking one message from the replay's iterator.
+enum Step {
+    Message(Gd<MCAPMessage>),
+    /// The next message is later (earlier when playing backwards) than the target time.
+    NotDue,
+    EndOfData,
+    EndOfRange,
+}
+
+/// What an [MCAPReplay] plays back: a single file or a merged multi-file session.
+enum ReplaySource {
+    Single(Gd<MCAPReader>),
+    Multi(Gd<MCAPMultiReader>),
+}
+
+impl ReplaySource {
+    fn stream_messages_iterator(&self) -> Gd<MCAPMessageIterator> {
+        match self {
+            ReplaySource::Single(r) => r.bind().stream_messages_iterator(),
+            ReplaySource::Multi(m) => m.bind().stream_messages_iterator(),
+        }
+    }
+
+    fn first_message_time_usec(&mut self) -> i64 {
+        match self {
+            ReplaySource::Single(r) => r.bind_mut().first_message_time_usec(),
+            ReplaySource::Multi(m) => m.bind_mut().first_message_time_usec(),
+        }
+    }
+
+    fn last_message_time_usec(&mut self) -> i64 {
+        match self {
+            ReplaySource::Single(r) => r.bind_mut().last_message_time_usec(),
+            ReplaySource::Multi(m) => m.bind_mut().last_message_time_usec(),
+        }
+    }
+
+    fn message_count_total(&mut self) -> i64 {
+        match self {
+            ReplaySource::Single(r) => r.bind_mut().message_count_total(),
+            ReplaySource::Multi(m) => m.bind_mut().message_count_total(),
+        }
+    }
+
+    fn refresh(&mut self) -> i64 {
+        match self {
+            ReplaySource::Single(r) => r.bind_mut().refresh(),
+            ReplaySource::Multi(m) => m.bind_mut().refresh(),
+        }
+    }
+}
+
+/// Logical replay time: log time moving with real time × speed (backwards for negative speeds)
+/// from an anchor, or moved
+/// explicitly by `advance()` for engine-delta and manual clocks. Pausing and speed changes move
+/// the anchor to the current position, so the playhead never jumps.
+#[derive(Default)]
+struct PlaybackClock {
+    // log time (usec) at `anchor_real`, or the frozen time while paused; None if not started
+    anchor_log: Option<u64>,
+    // real time of the anchor; None while paused or not driven by the wall clock
+    anchor_real: Option<Instant>,
+}
+
+impl PlaybackClock {
+    /// Start at `log_time`; with `ticking`, the time then advances with the wall clock.
+    fn start(&mut self, log_time: u64, ticking: bool) {
+        self.anchor_log = Some(log_time);
+        self.anchor_real = ticking.then(Instant::now);
+    }
+
+    fn reset(&mut self) {
+        self.anchor_log = None;
+        self.anchor_real = None;
+    }
+
+    fn is_started(&self) -> bool {
+        self.anchor_log.is_some()
+    }
+
+    fn is_ticking(&self) -> bool {
+        self.anchor_real.is_some()
+    }
+
+    /// Current log time (usec) at playback `speed`.
+    fn now(&self, speed: f64) -> Option<u64> {
+        let log = self.anchor_log?;
+        Some(match self.anchor_real {
+            Some(real) => log
+                .saturating_add_signed((real.elapsed().as_secs_f64() * 1_000_000.0 * speed) as i64),
+            None => log,
+        })
+    }
+
+    /// Stop following the wall clock, freezing the time at its current value.
+    fn pause(&mut self, speed: f64) {
+        self.anchor_log = self.now(speed);
+        self.anchor_real = None;
+    }
+
+    /// Follow the wall clock again from the frozen time.
+    fn resume(&mut self) {
+        if self.is_started() && !self.is_ticking() {
+            self.anchor_real = Some(Instant::now());
+        }
+    }
+
+    /// Move the time by `usec` of log time (backwards if negative).
+    fn advance(&mut self, usec: i64) {
+        if let Some(log) = &mut self.anchor_log {
+            *log = log.saturating_add_signed(usec);
+        }
+    }
+
+    /// Re-anchor at the current position before the speed changes from `old_speed`.
+    fn rebase(&mut self, old_speed: f64) {
+        if self.is_ticking() {
+            self.anchor_log = self.now(old_speed);
+            self.anchor_real = Some(Instant::now());
+        }
+    }
+}
+
 #[derive(GodotClass)]
-/// Node that replays MCAP messages from an [MCAPReader] in log-time order.
+/// Node that replays MCAP messages from an [MCAPReader] (or an [MCAPMultiReader]) in log-time order.
 ///
 /// Overview
 /// - Streams messages in the same order and relative timing as recorded in the MCAP file.
+/// - `set_multi_reader()` plays several files (e.g. rotated segments) back as one session.
 /// - Behaves similarly to Godot's Timer by managing internal processing (idle/physics) while running.
 /// - Optional channel filter and inclusive time range.
-/// - Supports playback speed (time scaling), seeking, and looping.
+/// - Supports playback speed (time scaling), reverse playback, seeking, and looping.
 /// - It is assumed that timestamps are in microseconds (usec).
 ///
 /// Properties
-/// - `speed: float` — Time scale (1.0 = real-time, 2.0 = double speed, 0.5 = half speed). Minimum 0.0 (clamped to 1.0 if <= 0).
+/// - `speed: float` — Time scale (1.0 = real-time, 2.0 = double speed, 0.5 = half speed, -1.0 = real-time backwards).
+///   0.0 is replaced by 1.0. Changing it during playback continues from the current position at the new rate.
 /// - `looping: bool` — If true, restarts playback upon reaching the end of the selected time range or data.
 /// - `processing_mode: ProcessingMode` — Whether to advance time in idle or physics.
+/// - `clock_mode: ReplayClockMode` — What drives the replay time: the wall clock (default), the
+///   engine's process/physics `delta` (respects `Engine.time_scale` and tree pausing), or only
+///   `advance()`/`advance_to()` calls (deterministic playback for tests and offline rendering).
+/// - `follow: bool` — Live mode for files that are still being written: at the end of the data,
+///   the reader is refreshed every `follow_poll_interval` seconds instead of stopping.
+/// - `frame_channel: int` — Channel id whose messages mark frames for `step_to_next_frame()`; -1 if unset.
+/// - `restore_state: bool` — On `start()`, `seek_to_time()` and when looping, first emit the latest
+///   message at or before the new time on every (filtered) channel, so slowly updating topics
+///   aren't stale after a jump.
 ///
 /// Signals
 /// - `message(MCAPMessage msg)` — Emitted each time a message becomes due according to the current logical replay time.
+/// - `paused()` / `resumed()` — Emitted by `pause()` and `resume()`.
+/// - `speed_changed(float speed)` — Emitted when `speed` changes.
+/// - `state_restored(Array[MCAPMessage] messages)` — With `restore_state`, emitted after the restored
+///   messages went through `message`, with the same messages.
+///
+/// Pausing
+/// - `pause()` freezes the replay time and stops processing; `resume()` continues from the same
+///   position. Seeking, filter and range changes while paused keep the replay paused.
+///
+/// State restoration
+/// - With `restore_state`, jumping to a time first emits, per channel, the latest message at or
+///   before it (oldest first) through `message`, then `state_restored` with all of them. Messages
+///   exactly at the new time are left to playback, which emits them right after.
+/// - The lookup uses the message indexes and only decompresses the chunks holding the restored
+///   messages, so it stays fast on long recordings. Messages before the time range count too.
+///
+/// Stepping
+/// - `step_next()`, `step_next_on_channel(id)`, `step_time(delta_usec)` and `step_to_next_frame()`
+///   move through the recording in playback direction, emit the passed messages through `message`
+///   and update `current_time_usec()`. They are meant for paused replays, which stay paused.
+/// - Steps respect the channel filter and time range, and never restart or stop the replay at the
+///   end: they just emit nothing more.
+/// - A frame is everything up to and including the next message on `frame_channel`.
+///
+/// ```gdscript
+/// replay.frame_channel = reader.topic_to_channel_id("/physics_tick")
+/// replay.pause()
+/// replay.step_to_next_frame()
+/// ```
+///
+/// Reverse playback
+/// - With a negative `speed`, the replay time runs backwards and messages are emitted in
+///   descending log-time order through the same `message` signal.
+/// - `start()` and looping begin at the end of the `set_time_range()` range (or the last message),
+///   and playback ends at its start; `seek_to_time(t)` emits the messages at `t` first.
+/// - Flipping the sign of `speed` during playback turns around at the current position; messages
+///   that are passed again are emitted again.
+/// - `follow` only waits for new data when playing forwards.
+///
+/// Clock modes
+/// - `CLOCK_MODE_WALL_CLOCK` (default): replay time follows real time × `speed`.
+/// - `CLOCK_MODE_ENGINE_DELTA`: each tick adds `delta` × `speed`, so the replay slows down with
+///   `Engine.time_scale` and stops while the tree is paused.
+/// - `CLOCK_MODE_MANUAL`: no internal processing; `advance(delta_usec)` and `advance_to(log_time_usec)`
+///   move the time (in the direction of `speed`) and emit the due messages before returning.
+/// - `advance()`/`advance_to()` also work in the other modes, skipping ahead.
+///
+/// ```gdscript
+/// replay.clock_mode = MCAPReplay.CLOCK_MODE_MANUAL
+/// replay.start()
+/// for frame in 600:
+///     replay.advance(16_667) # 60 fps, independent of real time
+///     render_frame()
+/// ```
 ///
 /// Basic usage (GDScript)
 /// ```gdscript
@@ -55,22 +261,30 @@ pub enum ProcessingMode {
 ///
 /// Notes
 /// - Requires a Summary section; if missing, `start()` returns false and no messages are emitted.
-/// - When `looping` is enabled, the replay restarts at `set_time_range()` start (if set) or at the file's first message time.
-/// - `current_time_usec()` returns the logical replay time = start time + elapsed real time × `speed`, clamped to `time_end` when set.
+/// - When `looping` is enabled, the replay restarts at `set_time_range()` start (if set) or at the file's first message time
+///   (the range end or last message time when playing backwards).
+/// - `current_time_usec()` returns the logical replay time: the time of the last seek, start, or speed change, plus the
+///   time played since × `speed` (per `clock_mode`), excluding pauses and clamped to the time range when set.
 /// - Channel filters with a single channel are optimized internally; multiple channels are filtered while iterating.
-/// - All times are in microseconds (usec).
+/// - In `follow` mode, `start()` also works on a file without messages yet; playback time is then
+///   anchored at the first message that arrives. At the end of the data, `follow` takes
+///   precedence over `looping`.
+/// - All times are in microseconds (usec). Files stored in other units are converted according to the
+///   reader's `time_unit`, so set it before calling `start()`.
 #[class(init, base=Node)]
 pub struct MCAPReplay {
     // immutable input
-    reader: Option<Gd<MCAPReader>>,
+    reader: Option<ReplaySource>,
     filter_channels: Option<HashSet<u16>>,
     time_start: Option<u64>,
     time_end: Option<u64>,
     // replay state
     running: bool,
-    #[export(range = (0.0, 10.0, or_greater))]
+    paused: bool,
+    #[init(val = 1.0)]
+    #[export(range = (-10.0, 10.0, or_greater, or_less))]
     #[var(set = set_speed)]
-    /// Playback speed (time scale); minimum 0.0 (clamped to 1.0 if <= 0).
+    /// Playback speed (time scale); negative values play backwards, 0.0 is replaced by 1.0.
     speed: f64,
     #[export]
     /// If true, restarts playback upon reaching the end of the selected time range or data.
@@ -80,125 +294,284 @@ pub struct MCAPReplay {
     #[var(set = set_processing_mode)]
     /// Whether to advance time in the _process (idle) or _physics_process (physics) callback.
     processing_mode: ProcessingMode,
+    #[init(val = ReplayClockMode::WALL_CLOCK)]
+    #[export]
+    #[var(set = set_clock_mode)]
+    /// What advances the replay time: wall clock, engine delta, or manual `advance()` calls.
+    clock_mode: ReplayClockMode,
+    #[export]
+    /// If true, waits for new data at the end instead of stopping (see `MCAPReader.refresh()`).
+    follow: bool,
+    #[init(val = 0.1)]
+    #[export(range = (0.0, 5.0, or_greater))]
+    /// Seconds between `MCAPReader.refresh()` calls while waiting for new data in follow mode.
+    follow_poll_interval: f64,
+    #[export]
+    /// If true, emits the latest earlier message of each channel on start, seek and loop (see `state_restored`).
+    restore_state: bool,
+    #[init(val = -1)]
+    #[export]
+    /// Channel whose messages mark frames for `step_to_next_frame()` (e.g. a per-physics-tick topic); -1 if unset.
+    frame_channel: i32,
+    last_follow_poll: Option<Instant>,
     iter: Option<Gd<MCAPMessageIterator>>,
-    start_real_time: Option<Instant>,
-    start_log_time: Option<u64>,
+    clock: PlaybackClock,
     base: Base<Node>,
 }
 
 impl MCAPReplay {
-    fn update_replay(&mut self) {
-        if !self.running {
-            return;
+    /// Refresh the reader if the poll interval has elapsed; returns true if new messages arrived.
+    fn poll_follow(&mut self) -> bool {
+        if let Some(last) = self.last_follow_poll
+            && last.elapsed().as_secs_f64() < self.follow_poll_interval
+        {
+            return false;
         }
-        let Some(start_rt) = self.start_real_time else {
-            return;
+        self.last_follow_poll = Some(Instant::now());
+        match &mut self.reader {
+            Some(r) => r.refresh() > 0,
+            None => false,
+        }
+    }
+
+    /// Whether the replay runs backwards (negative speed).
+    fn is_backward(&self) -> bool {
+        self.speed < 0.0
+    }
+
+    /// The next message in playback direction, without consuming it.
+    fn peek_step(&mut self, backward: bool) -> Option<Gd<MCAPMessage>> {
+        let it = self.iter.as_mut()?;
+        let mut it = it.bind_mut();
+        if backward {
+            it.peek_prev_message()
+        } else {
+            it.peek_message()
+        }
+    }
+
+    /// Consume the next message in playback direction.
+    fn take_step(&mut self, backward: bool) -> Option<Gd<MCAPMessage>> {
+        let it = self.iter.as_mut()?;
+        let mut it = it.bind_mut();
+        if backward {
+            it.get_prev_message()
+        } else {
+            it.get_next_message()
+        }
+    }
+
+    /// Where playback begins in the current direction: the range start (or first message time),
+    /// or the range end (or last message time) when playing backwards.
+    fn playback_start_time(&mut self) -> u64 {
+        let (bound, backward) = match self.is_backward() {
+            true => (self.time_end, true),
+            false => (self.time_start, false),
         };
-        let Some(start_lt) = self.start_log_time else {
+        if let Some(t) = bound {
+            return t;
+        }
+        match &mut self.reader {
+            Some(r) if backward => r.last_message_time_usec().max(0) as u64,
+            Some(r) => r.first_message_time_usec().max(0) as u64,
+            None => 0,
+        }
+    }
+
+    /// Position `it` at `t`, so that the messages at `t` come first in playback direction.
+    fn position_iterator(it: &mut Gd<MCAPMessageIterator>, t: u64, backward: bool) -> bool {
+        let mut it = it.bind_mut();
+        if !backward {
+            return it.seek_to_time(t as i64);
+        }
+        // Stand after the messages at `t`; past the last chunk, that is the end
+        if !it.seek_to_time((t as i64).saturating_add(1)) {
+            it.seek_to_end();
+        }
+        true
+    }
+
+    /// With `restore_state`, emit the latest message at or before `t` on each channel.
+    fn restore_state_at(&mut self, t: u64) {
+        if !self.restore_state {
+            return;
+        }
+        let Some(it) = &mut self.iter else {
             return;
         };
-
-        // Compute target log-time based on elapsed real time and speed
-        let elapsed = start_rt.elapsed();
-        let elapsed_us = (elapsed.as_secs_f64() * 1_000_000.0 * self.speed) as u64;
-        let mut target = start_lt.saturating_add(elapsed_us);
-        if let Some(t_end) = self.time_end {
-            if target > t_end {
-                target = t_end;
-            }
+        let latest = it.bind_mut().latest_messages_at(t as i64);
+        // Messages at `t` are emitted by playback right away
+        let restored: Array<Gd<MCAPMessage>> = latest
+            .iter_shared()
+            .filter(|m| m.bind().log_time.max(0) as u64 != t)
+            .collect();
+        for msg in restored.iter_shared() {
+            self.signals().message().emit(&msg);
         }
+        self.signals().state_restored().emit(&restored);
+    }
 
-        // Collect messages to emit up to target time
-        let mut to_emit: Vec<Gd<MCAPMessage>> = Vec::new();
-        enum EndAction {
-            None,
-            Stop,
-            Restart,
+    /// Whether the clock follows the wall clock right now.
+    fn wall_ticking(&self) -> bool {
+        self.clock_mode == ReplayClockMode::WALL_CLOCK && !self.paused
+    }
+
+    /// Process/physics tick: advance engine-driven clocks by `delta` seconds and emit due messages.
+    fn tick(&mut self, delta: f64) {
+        if !self.running || self.paused {
+            return;
         }
-        let mut action = EndAction::None;
+        if self.clock_mode == ReplayClockMode::ENGINE_DELTA {
+            self.clock
+                .advance((delta * 1_000_000.0 * self.speed) as i64);
+        }
+        self.update_replay();
+    }
 
-        loop {
-            let next_opt = {
-                match self.iter.as_mut() {
-                    Some(it) => it.bind_mut().peek_message(),
-                    None => None,
-                }
-            };
-            let Some(next) = next_opt else {
-                // End of stream
-                if self.looping {
-                    action = EndAction::Restart;
-                } else {
-                    action = EndAction::Stop;
-                }
-                break;
+    /// Emit the messages that are due at the current replay time; returns how many were emitted.
+    fn update_replay(&mut self) -> i64 {
+        if !self.running || self.paused {
+            return 0;
+        }
+        if self.follow && !self.clock.is_started() {
+            // Started on a file without messages: anchor playback at the first one to arrive
+            self.poll_follow();
+            let first = self
+                .iter
+                .as_mut()
+                .and_then(|it| it.bind_mut().peek_message());
+            let Some(first) = first else {
+                return 0;
             };
+            let ticking = self.wall_ticking();
+            self.clock
+                .start(first.bind().log_time.max(0) as u64, ticking);
+        }
+        self.emit_due(true)
+    }
 
-            // Check end/time bounds and channel filter
-            let nb = next.bind();
-            let msg_time = nb.log_time as u64;
-            drop(nb);
-
-            if let Some(t_end) = self.time_end {
-                if msg_time > t_end {
-                    action = if self.looping {
-                        EndAction::Restart
-                    } else {
-                        EndAction::Stop
-                    };
-                    break;
-                }
+    /// Emit the messages up to the clock's time. With `handle_end`, reaching the end of the data
+    /// or time range restarts (looping) or stops the replay; steps leave that to the next update.
+    fn emit_due(&mut self, handle_end: bool) -> i64 {
+        // Target log-time of the playback clock
+        let Some(mut target) = self.clock.now(self.speed) else {
+            return 0;
+        };
+        let backward = self.is_backward();
+        if backward {
+            if let Some(t_start) = self.time_start {
+                target = target.max(t_start);
             }
+        } else if let Some(t_end) = self.time_end {
+            target = target.min(t_end);
+        }
 
-            if msg_time <= target {
-                // Channel filter (optional multi-channel)
-                if let Some(chset) = &self.filter_channels {
-                    let ch_id = next.bind().channel.bind().id;
-                    if !chset.contains(&ch_id) {
-                        // consume and skip
-                        let _ = {
-                            if let Some(it) = self.iter.as_mut() {
-                                it.bind_mut().get_next_message()
-                            } else {
-                                None
-                            }
-                        };
+        // Collect messages to emit up to target time
+        let mut to_emit: Vec<Gd<MCAPMessage>> = Vec::new();
+        let mut at_end = false;
+        let mut polled = false;
+        loop {
+            match self.step_message(backward, Some(target)) {
+                Step::Message(msg) => to_emit.push(msg),
+                Step::NotDue => break,
+                // New data only ever arrives at the end
+                Step::EndOfData if self.follow && !backward => {
+                    // Wait for the writer; the iterator continues once the reader has new chunks
+                    if !polled && self.poll_follow() {
+                        polled = true;
                         continue;
                     }
+                    break;
                 }
-
-                // consume and emit
-                let msg_opt = {
-                    if let Some(it) = self.iter.as_mut() {
-                        it.bind_mut().get_next_message()
-                    } else {
-                        None
-                    }
-                };
-                if let Some(msg) = msg_opt {
-                    to_emit.push(msg);
-                } else {
+                Step::EndOfData | Step::EndOfRange => {
+                    at_end = true;
                     break;
                 }
-            } else {
-                break;
             }
         }
 
         // Now emit outside of iterator borrows
+        let emitted = to_emit.len() as i64;
         for msg in to_emit.into_iter() {
             self.signals().message().emit(&msg);
         }
 
-        match action {
-            EndAction::Restart => self.restart_from_range_start(),
-            EndAction::Stop => self.stop(),
-            EndAction::None => {}
+        if at_end && handle_end {
+            if self.looping {
+                self.restart_from_range_start();
+            } else {
+                self.stop();
+            }
+        }
+        emitted
+    }
+
+    /// Consume the next message in playback direction that passes the channel filter and, with a
+    /// `target`, is due by then. Does not consume anything at the end of the data or time range.
+    fn step_message(&mut self, backward: bool, target: Option<u64>) -> Step {
+        loop {
+            let Some(next) = self.peek_step(backward) else {
+                return Step::EndOfData;
+            };
+            // Check range bounds (in playback direction) and whether the message is due
+            let msg_time = next.bind().log_time as u64;
+            let out_of_range = if backward {
+                self.time_start.is_some_and(|t_start| msg_time < t_start)
+            } else {
+                self.time_end.is_some_and(|t_end| msg_time > t_end)
+            };
+            if out_of_range {
+                return Step::EndOfRange;
+            }
+            let not_due = target.is_some_and(|target| match backward {
+                true => msg_time < target,
+                false => msg_time > target,
+            });
+            if not_due {
+                return Step::NotDue;
+            }
+
+            // consume; skip messages outside the channel filter (optional multi-channel)
+            let Some(msg) = self.take_step(backward) else {
+                return Step::EndOfData;
+            };
+            if let Some(chset) = &self.filter_channels {
+                if !chset.contains(&msg.bind().channel.bind().id) {
+                    continue;
+                }
+            }
+            return Step::Message(msg);
+        }
+    }
+
+    /// Step to the next message in playback direction, checking for new data once in follow mode.
+    fn step_one(&mut self, backward: bool) -> Option<Gd<MCAPMessage>> {
+        match self.step_message(backward, None) {
+            Step::Message(msg) => Some(msg),
+            Step::EndOfData if self.follow && !backward => {
+                self.last_follow_poll = None;
+                if !self.poll_follow() {
+                    return None;
+                }
+                match self.step_message(backward, None) {
+                    Step::Message(msg) => Some(msg),
+                    _ => None,
+                }
+            }
+            _ => None,
         }
     }
 
+    /// Move the replay time to a stepped message and emit it.
+    fn emit_step(&mut self, msg: &Gd<MCAPMessage>) {
+        let t = msg.bind().log_time.max(0) as u64;
+        self.clock.start(t, self.wall_ticking());
+        self.signals().message().emit(msg);
+    }
+
     fn apply_process_state(&mut self) {
-        let running = self.running;
+        // A manual clock only moves in advance()/advance_to()
+        let running = self.running && !self.paused && self.clock_mode != ReplayClockMode::MANUAL;
         match self.processing_mode {
             ProcessingMode::IDLE => {
                 self.base_mut().set_process_internal(running);
@@ -212,43 +585,35 @@ impl MCAPReplay {
     }
 
     fn setup_iterator(&mut self, start_time: Option<u64>) -> bool {
-        let reader = match &self.reader {
-            Some(r) => r.clone(),
-            None => return false,
+        let Some(reader) = &self.reader else {
+            return false;
         };
         // Build a new iterator from reader
-        let mut it = reader.bind().stream_messages_iterator();
-        // Fast-path single-channel filter
+        let mut it = reader.stream_messages_iterator();
+        it.bind_mut().follow = self.follow;
+        // Let the iterator skip chunks without the filtered channels
         if let Some(set) = &self.filter_channels {
-            if set.len() == 1 {
-                if let Some(&cid) = set.iter().next() {
-                    it.bind_mut().for_channel(cid as i32);
-                }
-            }
+            let ids: PackedInt32Array = set.iter().map(|id| *id as i32).collect();
+            it.bind_mut().for_channels(ids);
         }
         if let Some(t) = start_time {
-            let _ = it.bind_mut().seek_to_time(t as i64);
+            let _ = Self::position_iterator(&mut it, t, self.is_backward());
         }
         self.iter = Some(it);
         true
     }
 
     fn restart_from_range_start(&mut self) {
-        // Determine new logical start time: explicit time_start or first available
-        let mut start_t: u64 = 0;
-        if let Some(s) = self.time_start {
-            start_t = s;
-        } else if let Some(r) = &self.reader {
-            start_t = r.clone().bind_mut().first_message_time_usec().max(0) as u64;
-        }
+        // Determine new logical start time: explicit range bound or first/last available
+        let start_t = self.playback_start_time();
         if !self.setup_iterator(Some(start_t)) {
             self.stop();
             return;
         }
-        self.start_log_time = Some(start_t);
-        self.start_real_time = Some(Instant::now());
+        self.clock.start(start_t, self.wall_ticking());
         self.running = true;
         self.apply_process_state();
+        self.restore_state_at(start_t);
     }
 }
 
@@ -258,11 +623,13 @@ impl INode for MCAPReplay {
         if what == NodeNotification::INTERNAL_PROCESS
             && self.processing_mode == ProcessingMode::IDLE
         {
-            self.update_replay();
+            let delta = self.base().get_process_delta_time();
+            self.tick(delta);
         } else if what == NodeNotification::INTERNAL_PHYSICS_PROCESS
             && self.processing_mode == ProcessingMode::PHYSICS
         {
-            self.update_replay();
+            let delta = self.base().get_physics_process_delta_time();
+            self.tick(delta);
         }
     }
 }
@@ -273,23 +640,55 @@ impl MCAPReplay {
     #[signal]
     pub fn message(msg: Gd<MCAPMessage>);
 
+    /// Emitted when replay is paused with `pause()`.
+    #[signal]
+    pub fn paused();
+
+    /// Emitted when a paused replay continues with `resume()`.
+    #[signal]
+    pub fn resumed();
+
+    /// Emitted when the playback speed changes.
+    #[signal]
+    pub fn speed_changed(speed: f64);
+
+    /// Emitted with `restore_state` after a jump, with the messages restored through `message`.
+    #[signal]
+    pub fn state_restored(messages: Array<Gd<MCAPMessage>>);
+
     /// Processing mode constant for idle updates.
     #[constant]
     const PROCESSING_MODE_IDLE: i64 = ProcessingMode::IDLE as i64;
     /// Processing mode constant for physics updates.
     #[constant]
     const PROCESSING_MODE_PHYSICS: i64 = ProcessingMode::PHYSICS as i64;
+    /// Clock mode constant: wall-clock time.
+    #[constant]
+    const CLOCK_MODE_WALL_CLOCK: i64 = ReplayClockMode::WALL_CLOCK as i64;
+    /// Clock mode constant: accumulated engine delta.
+    #[constant]
+    const CLOCK_MODE_ENGINE_DELTA: i64 = ReplayClockMode::ENGINE_DELTA as i64;
+    /// Clock mode constant: manual `advance()`/`advance_to()`.
+    #[constant]
+    const CLOCK_MODE_MANUAL: i64 = ReplayClockMode::MANUAL as i64;
 
     // --- Configuration API ---
 
     /// Set the reader used for replay. Resets iterator.
     #[func]
     pub fn set_reader(&mut self, reader: Gd<MCAPReader>) {
-        self.reader = Some(reader);
+        self.reader = Some(ReplaySource::Single(reader));
+        self.iter = None;
+    }
+
+    /// Replay a multi-file session instead of a single reader. Resets iterator.
+    #[func]
+    pub fn set_multi_reader(&mut self, reader: Gd<MCAPMultiReader>) {
+        self.reader = Some(ReplaySource::Multi(reader));
         self.iter = None;
     }
 
-    /// Clear the reader.
+    /// Clear the reader (single or multi-file).
     #[func]
     pub fn clear_reader(&mut self) {
         self.stop();
@@ -311,10 +710,9 @@ impl MCAPReplay {
         self.filter_channels = if set.is_empty() { None } else { Some(set) };
         // Rebuild iterator at current logical time if running
         if self.running {
-            let now = self.current_time_usec();
-            self.setup_iterator(Some(now.max(0) as u64));
-            self.start_log_time = Some(now.max(0) as u64);
-            self.start_real_time = Some(Instant::now());
+            let now = self.current_time_usec().max(0) as u64;
+            self.setup_iterator(Some(now));
+            self.clock.start(now, self.wall_ticking());
         }
     }
 
@@ -323,10 +721,9 @@ impl MCAPReplay {
     pub fn clear_filter_channels(&mut self) {
         self.filter_channels = None;
         if self.running {
-            let now = self.current_time_usec();
-            self.setup_iterator(Some(now.max(0) as u64));
-            self.start_log_time = Some(now.max(0) as u64);
-            self.start_real_time = Some(Instant::now());
+            let now = self.current_time_usec().max(0) as u64;
+            self.setup_iterator(Some(now));
+            self.clock.start(now, self.wall_ticking());
         }
     }
 
@@ -349,25 +746,43 @@ impl MCAPReplay {
     }
 
     /// Start replay. If a time range start is set, starts from there, else from file's first message time.
+    /// With a negative `speed`, starts from the range end or the file's last message time instead.
     #[func]
     pub fn start(&mut self) -> bool {
-        if self.reader.is_none() {
+        let Some(reader) = &mut self.reader else {
             return false;
+        };
+        let mut waiting_for_data = false;
+        if self.follow {
+            reader.refresh();
+            waiting_for_data = reader.message_count_total() == 0;
+            self.last_follow_poll = Some(Instant::now());
         }
-        // Determine start time
-        let mut start_t: u64 = 0;
-        if let Some(s) = self.time_start {
-            start_t = s;
-        } else if let Some(r) = &self.reader {
-            start_t = r.clone().bind_mut().first_message_time_usec().max(0) as u64;
+        // Determine start time (the range end when playing backwards)
+        let bound = match self.is_backward() {
+            true => self.time_end,
+            false => self.time_start,
+        };
+        if bound.is_some() {
+            waiting_for_data = false;
         }
-        if !self.setup_iterator(Some(start_t)) {
+        let start_t = match waiting_for_data {
+            true => 0,
+            false => self.playback_start_time(),
+        };
+        if !self.setup_iterator((!waiting_for_data).then_some(start_t)) {
             return false;
         }
-        self.start_log_time = Some(start_t);
-        self.start_real_time = Some(Instant::now());
+        self.clock.reset();
+        self.paused = false;
+        if !waiting_for_data {
+            self.clock.start(start_t, self.wall_ticking());
+        }
         self.running = true;
         self.apply_process_state();
+        if !waiting_for_data {
+            self.restore_state_at(start_t);
+        }
         true
     }
 
@@ -375,13 +790,111 @@ impl MCAPReplay {
     #[func]
     pub fn stop(&mut self) {
         self.running = false;
+        self.paused = false;
         self.apply_process_state();
         self.iter = None;
-        self.start_real_time = None;
-        self.start_log_time = None;
+        self.clock.reset();
     }
 
-    /// Seek to a specific log time (microseconds) and continue replay from there.
+    /// Pause a running replay: the replay time stops advancing and no messages are emitted until
+    /// `resume()`. Does nothing if not running or already paused.
+    #[func]
+    pub fn pause(&mut self) {
+        if !self.running || self.paused {
+            return;
+        }
+        self.clock.pause(self.speed);
+        self.paused = true;
+        self.apply_process_state();
+        self.signals().paused().emit();
+    }
+
+    /// Continue a paused replay from where it was paused. Does nothing if not paused.
+    #[func]
+    pub fn resume(&mut self) {
+        if !self.running || !self.paused {
+            return;
+        }
+        self.paused = false;
+        if self.wall_ticking() {
+            self.clock.resume();
+        }
+        self.apply_process_state();
+        self.signals().resumed().emit();
+    }
+
+    /// Return whether replay is running but paused.
+    #[func]
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Emit exactly the next message (in playback direction) and move the replay time to it.
+    /// Returns the message, or null at the end of the data or time range.
+    #[func]
+    pub fn step_next(&mut self) -> Option<Gd<MCAPMessage>> {
+        if !self.running {
+            return None;
+        }
+        let msg = self.step_one(self.is_backward())?;
+        self.emit_step(&msg);
+        Some(msg)
+    }
+
+    /// Step until the next message on `channel_id`, emitting it and every message passed on the way.
+    /// Returns the message on the channel, or null if the end was reached first.
+    #[func]
+    pub fn step_next_on_channel(&mut self, channel_id: i32) -> Option<Gd<MCAPMessage>> {
+        if !self.running || channel_id < 0 {
+            return None;
+        }
+        let ch_id = channel_id as u16;
+        if self
+            .filter_channels
+            .as_ref()
+            .is_some_and(|set| !set.contains(&ch_id))
+        {
+            godot_error!(
+                "MCAPReplay: channel {} is excluded by the channel filter",
+                channel_id
+            );
+            return None;
+        }
+        let backward = self.is_backward();
+        while let Some(msg) = self.step_one(backward) {
+            self.emit_step(&msg);
+            if msg.bind().channel.bind().id == ch_id {
+                return Some(msg);
+            }
+        }
+        None
+    }
+
+    /// Move the replay time by `delta_usec` of log time in playback direction and emit the messages
+    /// passed, without resuming. Returns the number of emitted messages.
+    #[func]
+    pub fn step_time(&mut self, delta_usec: i64) -> i64 {
+        if !self.running || !self.clock.is_started() {
+            return 0;
+        }
+        let delta = delta_usec.max(0);
+        self.clock
+            .advance(if self.is_backward() { -delta } else { delta });
+        self.emit_due(false)
+    }
+
+    /// Step to the next frame: emit the messages up to and including the next message on
+    /// `frame_channel`. Returns that marker message, or null if there is no further frame.
+    #[func]
+    pub fn step_to_next_frame(&mut self) -> Option<Gd<MCAPMessage>> {
+        if self.frame_channel < 0 {
+            godot_error!("MCAPReplay: set frame_channel before stepping by frames");
+            return None;
+        }
+        self.step_next_on_channel(self.frame_channel)
+    }
+
+    /// Seek to a specific log time (microseconds) and continue replay from there (in either direction).
     #[func]
     pub fn seek_to_time(&mut self, log_time_usec: i64) -> bool {
         let t = if log_time_usec < 0 {
@@ -389,16 +902,19 @@ impl MCAPReplay {
         } else {
             log_time_usec as u64
         };
-        if self.iter.is_none() && !self.setup_iterator(Some(t)) {
+        if self.iter.is_none() && !self.setup_iterator(None) {
             return false;
         }
+        let backward = self.is_backward();
         if let Some(it) = &mut self.iter {
-            if !it.bind_mut().seek_to_time(log_time_usec) {
+            if !Self::position_iterator(it, t, backward) {
                 return false;
             }
         }
-        self.start_log_time = Some(t);
-        self.start_real_time = Some(Instant::now());
+        self.clock.start(t, self.wall_ticking());
+        if self.running {
+            self.restore_state_at(t);
+        }
         true
     }
 
@@ -411,25 +927,75 @@ impl MCAPReplay {
     /// Get the current logical replay time in microseconds. Returns -1 if not started.
     #[func]
     pub fn current_time_usec(&self) -> i64 {
-        match (self.start_log_time, self.start_real_time) {
-            (Some(sl), Some(sr)) => {
-                let elapsed_us = (sr.elapsed().as_secs_f64() * 1_000_000.0 * self.speed) as i64;
-                let mut cur = sl as i64 + elapsed_us;
+        match self.clock.now(self.speed) {
+            Some(mut cur) => {
                 if let Some(e) = self.time_end {
-                    if cur as u64 > e {
-                        cur = e as i64;
-                    }
+                    cur = cur.min(e);
                 }
-                cur
+                if let Some(s) = self.time_start {
+                    cur = cur.max(s);
+                }
+                cur as i64
             }
-            _ => -1,
+            None => -1,
         }
     }
 
-    /// Set playback speed (1.0 = real-time, 2.0 = double speed, etc.).
+    /// Set playback speed (1.0 = real-time, 2.0 = double speed, -1.0 = backwards, etc.). During
+    /// playback, the replay continues from its current position at the new speed and direction.
     #[func]
     pub fn set_speed(&mut self, speed: f64) {
-        self.speed = if speed <= 0.0 { 1.0 } else { speed };
+        let speed = if speed == 0.0 { 1.0 } else { speed };
+        if speed == self.speed {
+            return;
+        }
+        self.clock.rebase(self.speed);
+        self.speed = speed;
+        self.signals().speed_changed().emit(speed);
+    }
+
+    /// Set what advances the replay time. The replay time is kept when switching during playback.
+    #[func]
+    pub fn set_clock_mode(&mut self, mode: ReplayClockMode) {
+        self.clock.pause(self.speed);
+        self.clock_mode = mode;
+        if self.running && self.wall_ticking() {
+            self.clock.resume();
+        }
+        self.apply_process_state();
+    }
+
+    /// Move the replay time by `delta_usec` of real time (scaled by `speed`, so backwards for a
+    /// negative speed) and emit the messages that became due, before returning. Meant for
+    /// `CLOCK_MODE_MANUAL`; in the other modes it skips ahead. Returns the number of emitted
+    /// messages (0 if not running or paused).
+    #[func]
+    pub fn advance(&mut self, delta_usec: i64) -> i64 {
+        if !self.running || self.paused {
+            return 0;
+        }
+        self.clock
+            .advance((delta_usec.max(0) as f64 * self.speed) as i64);
+        self.update_replay()
+    }
+
+    /// Move the replay time to `log_time_usec` and emit the messages that became due. Times behind
+    /// the current position in playback direction are ignored (use `seek_to_time()` to go back).
+    /// Returns the number of emitted messages.
+    #[func]
+    pub fn advance_to(&mut self, log_time_usec: i64) -> i64 {
+        if !self.running || self.paused {
+            return 0;
+        }
+        let now = self.clock.now(self.speed).unwrap_or(0);
+        let target = log_time_usec.max(0) as u64;
+        if !self.clock.is_started() {
+            // Waiting for the first message in follow mode: start the clock there
+            self.clock.start(target, self.wall_ticking());
+        } else if (target > now && !self.is_backward()) || (target < now && self.is_backward()) {
+            self.clock.advance(target as i64 - now as i64);
+        }
+        self.update_replay()
     }
 
     /// Set processing mode to use either idle or physics ticks.
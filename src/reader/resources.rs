@@ -0,0 +1,56 @@
+use crate::types::*;
+use godot::prelude::*;
+use mcap::{Channel, Message, Schema};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Channel and schema Resources of one reader (or `MCAPMultiReader` session), built once per ID
+/// and handed out to every message, iterator and replay reading through it.
+///
+/// Entries are looked up by ID only; whoever replaces the summary with one whose IDs may mean
+/// something else (loading another file, `MCAPMultiReader.remap()`) must `clear()` the cache.
+#[derive(Default)]
+pub(super) struct ResourceCache {
+    channels: RefCell<HashMap<u16, Gd<MCAPChannel>>>,
+    schemas: RefCell<HashMap<u16, Gd<MCAPSchema>>>,
+}
+
+pub(super) type SharedResources = Rc<ResourceCache>;
+
+impl ResourceCache {
+    pub fn new_shared() -> SharedResources {
+        Rc::new(Self::default())
+    }
+
+    /// The Resource for `channel`, created on first use.
+    pub fn channel(&self, channel: &Channel) -> Gd<MCAPChannel> {
+        if let Some(gd) = self.channels.borrow().get(&channel.id) {
+            return gd.clone();
+        }
+        let schema = channel.schema.as_ref().map(|s| self.schema(s));
+        let gd = MCAPChannel::from_mcap(channel, schema);
+        self.channels.borrow_mut().insert(channel.id, gd.clone());
+        gd
+    }
+
+    /// The Resource for `schema`, created on first use.
+    pub fn schema(&self, schema: &Schema) -> Gd<MCAPSchema> {
+        if let Some(gd) = self.schemas.borrow().get(&schema.id) {
+            return gd.clone();
+        }
+        let gd = MCAPSchema::from_mcap(schema);
+        self.schemas.borrow_mut().insert(schema.id, gd.clone());
+        gd
+    }
+
+    /// Build a message sharing the cached channel Resource.
+    pub fn message(&self, msg: &Message, unit: MCAPTimeUnit) -> Gd<MCAPMessage> {
+        MCAPMessage::from_mcap(msg, self.channel(&msg.channel), unit)
+    }
+
+    pub fn clear(&self) {
+        self.channels.borrow_mut().clear();
+        self.schemas.borrow_mut().clear();
+    }
+}
@@ -0,0 +1,311 @@
+use crate::writer::{MergedSummary, WriterSink};
+use mcap::read::ChunkReader;
+use mcap::records::{Header, Metadata, Record};
+use mcap::{Attachment, Channel, MAGIC, Message, Schema, Writer, parse_record};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+
+/// Why `DataScan::handle` stopped: damage ends the scan, visitor errors abort it.
+enum Stop {
+    Damaged(String),
+    Visitor(String),
+}
+
+/// A top-level record of an MCAP file, located but not parsed.
+pub(crate) struct Frame<'a> {
+    /// Offset of the record's opcode in the file.
+    pub offset: usize,
+    pub opcode: u8,
+    pub body: &'a [u8],
+}
+
+/// Walks the top-level records of an MCAP file by their length prefixes.
+///
+/// Yields an error (and then stops) at a record that is cut off by the end of the buffer. The
+/// trailing magic of a finalized file is reported as such an error, so callers stop at DataEnd.
+pub(crate) struct Frames<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Frames<'a> {
+    pub fn new(buf: &'a [u8]) -> Result<Self, String> {
+        if !buf.starts_with(MAGIC) {
+            return Err("not an MCAP file: missing magic bytes".into());
+        }
+        Ok(Self {
+            buf,
+            pos: MAGIC.len(),
+        })
+    }
+
+    /// Continue walking at `pos`, which must be the start of a record found by an earlier walk.
+    pub fn resume(buf: &'a [u8], pos: usize) -> Self {
+        Self { buf, pos }
+    }
+}
+
+impl<'a> Iterator for Frames<'a> {
+    type Item = Result<Frame<'a>, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let pos = self.pos;
+        let rest = self.buf.get(pos..).filter(|r| !r.is_empty())?;
+        // Nothing is yielded after an error
+        self.pos = self.buf.len();
+        // opcode (u8) + record length (u64 LE) + body
+        if rest.len() < 9 {
+            return Some(Err(format!("truncated record header at offset {}", pos)));
+        }
+        let opcode = rest[0];
+        let len = u64::from_le_bytes(rest[1..9].try_into().unwrap());
+        let Some(body) = usize::try_from(len)
+            .ok()
+            .and_then(|len| rest.get(9..9usize.checked_add(len)?))
+        else {
+            return Some(Err(format!(
+                "truncated record (opcode {:#04x}, {} bytes) at offset {}",
+                opcode, len, pos
+            )));
+        };
+        self.pos = pos + 9 + body.len();
+        Some(Ok(Frame {
+            offset: pos,
+            opcode,
+            body,
+        }))
+    }
+}
+
+/// Time-bearing or standalone record found while scanning a data section.
+pub(crate) enum DataRecord<'a> {
+    Message(Message<'a>),
+    Attachment(Attachment<'a>),
+    Metadata(Metadata),
+}
+
+/// Linear scan over the data section of an MCAP file that does not rely on the summary.
+///
+/// Top-level records are framed by hand so a truncated tail can be told apart from the intact
+/// part of the file; chunks are decompressed on the fly and schemas/channels are wired together
+/// as they appear. This works for finalized files as well as files that were never closed. The
+/// scan stops at the DataEnd record, at the end of the buffer, or at the first damaged or
+/// incomplete record (see `error`).
+pub(crate) struct DataScan<'a> {
+    pub header: Option<Header>,
+    pub schemas: BTreeMap<u16, Arc<Schema<'a>>>,
+    pub channels: BTreeMap<u16, Arc<Channel<'a>>>,
+    pub messages: u64,
+    pub attachments: u64,
+    pub metadata: u64,
+    /// Number of top-level bytes (including the leading magic) that were read without damage.
+    pub valid_len: usize,
+    /// Whether the scan reached a DataEnd record (i.e. the file was finalized).
+    pub reached_data_end: bool,
+    /// Why the scan stopped early, if it hit a truncated or corrupt record.
+    pub error: Option<String>,
+}
+
+impl<'a> DataScan<'a> {
+    /// Scan `buf` and hand every message, attachment and metadata record to `visit`.
+    /// Errors returned by `visit` abort the scan and are passed through as `Err`.
+    pub fn run<F>(buf: &'a [u8], mut visit: F) -> Result<Self, String>
+    where
+        F: FnMut(&Self, DataRecord<'a>) -> Result<(), String>,
+    {
+        let mut scan = Self {
+            header: None,
+            schemas: BTreeMap::new(),
+            channels: BTreeMap::new(),
+            messages: 0,
+            attachments: 0,
+            metadata: 0,
+            valid_len: 0,
+            reached_data_end: false,
+            error: None,
+        };
+        let frames = match Frames::new(buf) {
+            Ok(f) => f,
+            Err(e) => {
+                scan.error = Some(e);
+                return Ok(scan);
+            }
+        };
+        scan.valid_len = MAGIC.len();
+
+        for frame in frames {
+            let Frame {
+                offset: pos,
+                opcode,
+                body,
+            } = match frame {
+                Ok(f) => f,
+                Err(e) => {
+                    scan.error = Some(e);
+                    break;
+                }
+            };
+            let record = match parse_record(opcode, body) {
+                Ok(r) => r,
+                Err(e) => {
+                    scan.error = Some(format!("damaged record at offset {}: {}", pos, e));
+                    break;
+                }
+            };
+
+            let result = match record {
+                Record::Header(h) => {
+                    scan.header = Some(h);
+                    Ok(())
+                }
+                Record::Chunk { header, data } => {
+                    let records = match ChunkReader::new(header, &data) {
+                        Ok(r) => r,
+                        Err(e) => {
+                            scan.error = Some(format!("damaged chunk at offset {}: {}", pos, e));
+                            break;
+                        }
+                    };
+                    let mut result = Ok(());
+                    for item in records {
+                        result = match item {
+                            Ok(r) => scan.handle(r.into_owned(), &mut visit),
+                            Err(e) => Err(Stop::Damaged(format!(
+                                "damaged chunk at offset {}: {}",
+                                pos, e
+                            ))),
+                        };
+                        if result.is_err() {
+                            break;
+                        }
+                    }
+                    result
+                }
+                Record::DataEnd(_) => {
+                    scan.reached_data_end = true;
+                    Ok(())
+                }
+                r => scan.handle(r, &mut visit),
+            };
+            match result {
+                Ok(()) => {}
+                Err(Stop::Damaged(e)) => {
+                    scan.error = Some(e);
+                    break;
+                }
+                Err(Stop::Visitor(e)) => return Err(e),
+            }
+
+            scan.valid_len = pos + 9 + body.len();
+            if scan.reached_data_end {
+                break;
+            }
+        }
+        Ok(scan)
+    }
+
+    /// Register a schema/channel or hand a data record to `visit`.
+    fn handle<F>(&mut self, record: Record<'a>, visit: &mut F) -> Result<(), Stop>
+    where
+        F: FnMut(&Self, DataRecord<'a>) -> Result<(), String>,
+    {
+        let data = match record {
+            Record::Schema { header, data } => {
+                let schema = Schema {
+                    id: header.id,
+                    name: header.name,
+                    encoding: header.encoding,
+                    data,
+                };
+                self.schemas.insert(schema.id, Arc::new(schema));
+                return Ok(());
+            }
+            Record::Channel(ch) => {
+                let schema = match ch.schema_id {
+                    0 => None,
+                    id => match self.schemas.get(&id) {
+                        Some(s) => Some(s.clone()),
+                        None => {
+                            return Err(Stop::Damaged(format!(
+                                "channel {} references unknown schema {}",
+                                ch.id, id
+                            )));
+                        }
+                    },
+                };
+                let channel = Channel {
+                    id: ch.id,
+                    topic: ch.topic,
+                    schema,
+                    message_encoding: ch.message_encoding,
+                    metadata: ch.metadata,
+                };
+                self.channels.insert(channel.id, Arc::new(channel));
+                return Ok(());
+            }
+            Record::Message { header, data } => {
+                let Some(channel) = self.channels.get(&header.channel_id) else {
+                    return Err(Stop::Damaged(format!(
+                        "message references unknown channel {}",
+                        header.channel_id
+                    )));
+                };
+                self.messages += 1;
+                DataRecord::Message(Message {
+                    channel: channel.clone(),
+                    sequence: header.sequence,
+                    log_time: header.log_time,
+                    publish_time: header.publish_time,
+                    data,
+                })
+            }
+            Record::Attachment { header, data, .. } => {
+                self.attachments += 1;
+                DataRecord::Attachment(Attachment {
+                    log_time: header.log_time,
+                    create_time: header.create_time,
+                    name: header.name,
+                    media_type: header.media_type,
+                    data,
+                })
+            }
+            Record::Metadata(m) => {
+                self.metadata += 1;
+                DataRecord::Metadata(m)
+            }
+            _ => return Ok(()),
+        };
+        visit(self, data).map_err(Stop::Visitor)
+    }
+}
+
+/// Copy every intact schema, channel, message, attachment and metadata record of `buf` into `w`.
+///
+/// `w` must be a fresh writer created on the sink `merged` tracks. Schema and channel IDs are
+/// kept; channels without any message and schemas without any channel are carried over as well.
+/// Returns the scan so callers can report what was salvaged.
+pub(crate) fn copy_records<'a>(
+    buf: &'a [u8],
+    w: &mut Writer<WriterSink>,
+    merged: &mut MergedSummary,
+) -> Result<DataScan<'a>, String> {
+    // Messages carry their channel (and schema), which registers them with their original IDs
+    let mut used_channels: HashSet<u16> = HashSet::new();
+    let scan = DataScan::run(buf, |_, record| {
+        match record {
+            DataRecord::Message(m) => {
+                used_channels.insert(m.channel.id);
+                merged.note_message(m.log_time);
+                w.write(&m)
+            }
+            DataRecord::Attachment(a) => w.attach(&a),
+            DataRecord::Metadata(m) => w.write_metadata(&m),
+        }
+        .map_err(|e| e.to_string())
+    })?;
+
+    let channels: Vec<_> = scan.channels.values().cloned().collect();
+    let schemas: Vec<_> = scan.schemas.values().cloned().collect();
+    merged.reserve_ids(w, &channels, &schemas, &used_channels, false)?;
+    Ok(scan)
+}
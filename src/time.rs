@@ -0,0 +1,121 @@
+use crate::types::*;
+use godot::classes::{Engine, Time};
+use godot::prelude::*;
+use std::cell::RefCell;
+use std::mem::ManuallyDrop;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+impl MCAPTimeUnit {
+    /// Convert a microsecond time into this unit, failing if it doesn't fit into u64.
+    pub fn usec_to_unit(self, usec: u64) -> Result<u64, String> {
+        match self {
+            MCAPTimeUnit::Microseconds => Ok(usec),
+            MCAPTimeUnit::Nanoseconds => usec
+                .checked_mul(1_000)
+                .ok_or_else(|| format!("{usec} usec overflows when converted to nanoseconds")),
+            MCAPTimeUnit::Milliseconds => Ok(usec / 1_000),
+        }
+    }
+
+    /// Like `usec_to_unit`, but clamps instead of failing (used for query bounds).
+    pub fn usec_to_unit_saturating(self, usec: u64) -> u64 {
+        match self {
+            MCAPTimeUnit::Nanoseconds => usec.saturating_mul(1_000),
+            _ => self.usec_to_unit(usec).unwrap_or(u64::MAX),
+        }
+    }
+
+    /// Like `usec_to_unit_saturating`, but rounds up for coarser units (used for lower query bounds).
+    pub fn usec_to_unit_ceil(self, usec: u64) -> u64 {
+        match self {
+            MCAPTimeUnit::Milliseconds => usec.div_ceil(1_000),
+            _ => self.usec_to_unit_saturating(usec),
+        }
+    }
+
+    /// Convert a time stored in this unit to microseconds (nanoseconds are truncated).
+    pub fn to_usec(self, t: u64) -> u64 {
+        match self {
+            MCAPTimeUnit::Microseconds => t,
+            MCAPTimeUnit::Nanoseconds => t / 1_000,
+            MCAPTimeUnit::Milliseconds => t.saturating_mul(1_000),
+        }
+    }
+}
+
+thread_local! {
+    // Clock of the most recently opened writer. Never dropped, so a custom Callable is not released
+    // after the engine has shut down.
+    static ACTIVE_CLOCK: RefCell<ManuallyDrop<Clock>> = RefCell::new(ManuallyDrop::new(Clock::default()));
+}
+
+/// Clock a writer stamps messages with, captured from `MCAPWriteOptions` at `open()`.
+#[derive(Clone)]
+pub struct Clock {
+    source: MCAPClockSource,
+    custom: Callable,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self {
+            source: MCAPClockSource::EngineTicks,
+            custom: Callable::invalid(),
+        }
+    }
+}
+
+impl Clock {
+    pub fn from_options(opts: &MCAPWriteOptions) -> Self {
+        Self {
+            source: opts.clock_source,
+            custom: opts.custom_clock.clone(),
+        }
+    }
+
+    /// Make this the clock used by the `create()` helpers of `MCAPMessage`, `MCAPAttachment` and
+    /// `MCAPMessageHeader`. Writers call this when they open a file.
+    pub fn make_active(&self) {
+        ACTIVE_CLOCK.with(|active| {
+            let old = std::mem::replace(&mut *active.borrow_mut(), ManuallyDrop::new(self.clone()));
+            drop(ManuallyDrop::into_inner(old));
+        });
+    }
+
+    /// Current time in microseconds of the clock of the most recently opened writer (engine ticks
+    /// until a writer has been opened).
+    pub fn active_now_usec() -> u64 {
+        // Clone first: a custom clock may open a writer, which would replace the active clock
+        let clock = ACTIVE_CLOCK.with(|active| Clock::clone(&active.borrow()));
+        clock.now_usec()
+    }
+
+    /// Current time of this clock in microseconds.
+    pub fn now_usec(&self) -> u64 {
+        match self.source {
+            MCAPClockSource::EngineTicks => Time::singleton().get_ticks_usec(),
+            MCAPClockSource::UnixWall => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_micros() as u64)
+                .unwrap_or(0),
+            MCAPClockSource::PhysicsFrame => {
+                let engine = Engine::singleton();
+                let tps = engine.get_physics_ticks_per_second().max(1) as u64;
+                engine.get_physics_frames().saturating_mul(1_000_000) / tps
+            }
+            MCAPClockSource::Custom => {
+                if !self.custom.is_valid() {
+                    godot_error!("custom_clock is not a valid Callable; using engine ticks");
+                    return Time::singleton().get_ticks_usec();
+                }
+                match self.custom.call(&[]).try_to::<i64>() {
+                    Ok(t) => t.max(0) as u64,
+                    Err(e) => {
+                        godot_error!("custom_clock must return an int (usec): {}", e);
+                        Time::singleton().get_ticks_usec()
+                    }
+                }
+            }
+        }
+    }
+}
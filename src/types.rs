@@ -14,6 +14,50 @@ pub enum MCAPCompression {
     Lz4,
 }
 
+/// Unit of the timestamps stored in an MCAP file.
+///
+/// The Godot-facing API always works in microseconds; this only controls how times are encoded
+/// in (and decoded from) the file.
+#[derive(GodotConvert, Var, Export, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[godot(via = i64)]
+pub enum MCAPTimeUnit {
+    /// Microseconds (the extension's historical default).
+    #[default]
+    Microseconds,
+    /// Nanoseconds, as expected by Foxglove and ROS 2 tooling.
+    Nanoseconds,
+    /// Milliseconds.
+    Milliseconds,
+}
+
+/// Clock used by writers when they stamp messages themselves.
+#[derive(GodotConvert, Var, Export, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[godot(via = i64)]
+pub enum MCAPClockSource {
+    /// `Time.get_ticks_usec()`: microseconds since engine start.
+    #[default]
+    EngineTicks,
+    /// Microseconds since the Unix epoch (system wall clock).
+    UnixWall,
+    /// Physics frame count converted to microseconds using the physics tick rate.
+    PhysicsFrame,
+    /// Value returned by `custom_clock` (a Callable returning microseconds as int).
+    Custom,
+}
+
+/// Order in which `MCAPMessageIterator` yields messages.
+#[derive(GodotConvert, Var, Export, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[godot(via = i64)]
+pub enum MCAPMessageOrder {
+    /// By log time, merging chunks that overlap in time.
+    #[default]
+    LogTime,
+    /// By publish time (chunks are still loaded by their log-time range).
+    PublishTime,
+    /// As stored: chunk by chunk in summary order, messages in their order within the chunk.
+    File,
+}
+
 #[derive(GodotClass)]
 #[class(base=Resource)]
 pub struct MCAPWriteOptions {
@@ -53,6 +97,16 @@ pub struct MCAPWriteOptions {
     pub calculate_summary_section_crc: bool,
     #[export]
     pub calculate_attachment_crcs: bool,
+    /// Unit of the timestamps stored in the file; script-facing times stay in microseconds.
+    #[export]
+    pub time_unit: MCAPTimeUnit,
+    /// Clock used when the writer stamps messages itself (channel handles, `set_timestamp_offset_to_now()`).
+    /// Once the writer is opened, `MCAPMessage.create()` and friends stamp with it as well.
+    #[export]
+    pub clock_source: MCAPClockSource,
+    /// Callable returning the current time in microseconds, used with `MCAPClockSource.Custom`.
+    #[var]
+    pub custom_clock: Callable,
     #[cfg(any(feature = "zstd", feature = "lz4"))]
     #[export]
     pub compression_level: u32,
@@ -161,9 +215,10 @@ pub struct MCAPSummary {
 
 /// Describes a schema used by one or more [MCAPChannel]s in an MCAP file.
 ///
-/// Identity note: Instances returned by the reader are newly constructed on
-/// each call/iteration. Do not compare by object identity/reference; prefer
-/// comparing by stable fields such as `id` (and optionally `name`/`encoding`).
+/// Identity note: A reader creates one instance per schema id and returns it
+/// from every call/iteration (also as `channel.schema`), so instances from the
+/// same reader compare equal by reference. Instances from different readers
+/// differ; compare those by `id` (and optionally `name`/`encoding`).
 #[derive(GodotClass)]
 #[class(no_init, base=Resource)]
 pub struct MCAPSchema {
@@ -183,9 +238,10 @@ pub struct MCAPSchema {
 
 /// Describes a channel which [Message]s are published to in an MCAP file.
 ///
-/// Identity note: Reader methods and iterators construct new `MCAPChannel`
-/// instances as needed. Compare channels by `id` (preferred) or by `topic`,
-/// not by object identity.
+/// Identity note: A reader (or multi reader session) creates one `MCAPChannel`
+/// per channel id and shares it between its methods, iterators and replays, so
+/// `msg.channel == channel` works within one reader. Treat it as read-only.
+/// Across readers, compare channels by `id` or `topic`.
 #[derive(GodotClass)]
 #[class(no_init, base=Resource)]
 pub struct MCAPChannel {
@@ -208,10 +264,10 @@ pub struct MCAPChannel {
 
 /// An event in an MCAP file, published to a [MCAPChannel].
 ///
-/// Identity note: The embedded `channel` Resource is newly constructed and may
-/// be a distinct instance from channels returned by other reads/iterations.
-/// Compare messages by fields like `channel.id` together with `sequence` or
-/// `log_time`, rather than by object identity.
+/// Identity note: Messages are newly constructed on each read, but the embedded
+/// `channel` Resource is shared with every other message on that channel read
+/// through the same reader. Compare messages by fields like `channel.id`
+/// together with `sequence` or `log_time`, rather than by object identity.
 #[derive(GodotClass)]
 #[class(no_init, base=Resource)]
 pub struct MCAPMessage {
@@ -289,3 +345,74 @@ pub struct MCAPMetadata {
     /// Only string key/value pairs are supported.
     pub metadata: Dictionary,
 }
+
+/// Result of `MCAPReader.recover()`: what was salvaged from a damaged or unfinalized file.
+#[derive(GodotClass)]
+#[class(no_init, base=Resource)]
+pub struct MCAPRecoveryReport {
+    #[export]
+    /// True if a valid file was written.
+    pub ok: bool,
+    #[export]
+    /// Why recovery failed (empty on success).
+    pub error: GString,
+    #[export]
+    /// True if the input already ended its data section properly (it may still lack a summary).
+    pub was_finalized: bool,
+    #[export]
+    /// Why the scan stopped early (truncated or corrupt record); empty if the data was intact.
+    pub damage: GString,
+    #[export]
+    pub message_count: i64,
+    #[export]
+    pub attachment_count: i64,
+    #[export]
+    pub metadata_count: i64,
+    #[export]
+    pub schema_count: i64,
+    #[export]
+    pub channel_count: i64,
+    #[export]
+    /// Size of the input file.
+    pub bytes_in: i64,
+    #[export]
+    /// Length of the intact prefix of the input that was salvaged.
+    pub bytes_salvaged: i64,
+    #[export]
+    /// Length of the dropped tail (partial or corrupt records). A partially decodable chunk at
+    /// the end counts as lost even though the messages before the damage were kept.
+    pub bytes_lost: i64,
+    #[export]
+    /// Size of the written file.
+    pub bytes_out: i64,
+}
+
+/// Result of `MCAPReader.extract_columns()`/`extract_fields()`: one row per message, in log-time
+/// order, stored column by column.
+#[derive(GodotClass)]
+#[class(no_init, base=Resource)]
+pub struct MCAPColumns {
+    #[export]
+    pub channel_ids: PackedInt32Array,
+    #[export]
+    /// Log timestamps in microseconds.
+    pub log_times: PackedInt64Array,
+    #[export]
+    /// Publish timestamps in microseconds.
+    pub publish_times: PackedInt64Array,
+    #[export]
+    /// Sequence numbers (values above 2^31 - 1 wrap to negative numbers).
+    pub sequences: PackedInt32Array,
+    #[export]
+    /// All payloads back to back; empty unless payloads were requested.
+    pub payload: PackedByteArray,
+    #[export]
+    /// Row count + 1 offsets into `payload`: row `i` is `payload.slice(payload_offsets[i], payload_offsets[i + 1])`.
+    pub payload_offsets: PackedInt64Array,
+    #[export]
+    /// Decoded layout fields by name (see `MCAPReader.extract_fields()`).
+    pub fields: Dictionary,
+    #[export]
+    /// Rows whose payload was too short for the layout; their missing fields are zero.
+    pub short_payload_count: i64,
+}
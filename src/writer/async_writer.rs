@@ -0,0 +1,822 @@
+use crate::time::Clock;
+use crate::types::*;
+use crate::util::dict_to_btreemap;
+use crate::writer::mcap_writer::to_file_time;
+use godot::classes::{ProjectSettings, RefCounted};
+use godot::prelude::*;
+use mcap::records::{MessageHeader as McapMessageHeader, Metadata};
+use mcap::{Attachment as McapAttachment, Message as McapMessage, Writer};
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, mpsc};
+use std::thread::JoinHandle;
+
+#[derive(GodotConvert, Var, Export, PartialEq, Debug, Clone, Copy)]
+#[godot(via = i64)]
+/// What [MCAPAsyncWriter] does when a message is queued while the queue is full.
+///
+/// Only messages are subject to the policy; schemas, channels, attachments, metadata and
+/// flushes always wait for a free slot so the file stays consistent.
+pub enum BackpressurePolicy {
+    /// Wait on the calling thread until the worker frees a slot (default).
+    Block,
+    /// Discard the oldest queued message to make room for the new one.
+    DropOldest,
+    /// Discard the message being queued.
+    DropNewest,
+}
+
+/// Work items handed from the calling thread to the writer thread.
+enum Command {
+    AddSchema {
+        name: String,
+        encoding: String,
+        data: Vec<u8>,
+        reply: mpsc::Sender<Result<u16, String>>,
+    },
+    AddChannel {
+        schema_id: u16,
+        topic: String,
+        message_encoding: String,
+        metadata: BTreeMap<String, String>,
+        reply: mpsc::Sender<Result<u16, String>>,
+    },
+    Message(McapMessage<'static>),
+    KnownChannelMessage {
+        header: McapMessageHeader,
+        data: Vec<u8>,
+    },
+    Attachment(McapAttachment<'static>),
+    Metadata(Metadata),
+    Flush,
+}
+
+impl Command {
+    #[inline]
+    fn is_message(&self) -> bool {
+        matches!(
+            self,
+            Command::Message(_) | Command::KnownChannelMessage { .. }
+        )
+    }
+
+    #[inline]
+    fn is_time_bearing(&self) -> bool {
+        matches!(
+            self,
+            Command::Message(_) | Command::KnownChannelMessage { .. } | Command::Attachment(_)
+        )
+    }
+}
+
+enum PushOutcome {
+    Queued,
+    Dropped,
+    Closed,
+    WorkerStopped,
+}
+
+struct QueueState {
+    items: VecDeque<Command>,
+    closed: bool,
+    // Set once the writer thread has exited, including by panicking; nothing drains the queue.
+    worker_stopped: bool,
+}
+
+// State shared between the Godot-facing object and its writer thread.
+struct Shared {
+    state: Mutex<QueueState>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    capacity: usize,
+    written: AtomicU64,
+    dropped: AtomicU64,
+    failed: AtomicU64,
+    // Most recent error raised on the writer thread, surfaced on the next call from Godot.
+    worker_error: Mutex<Option<String>>,
+}
+
+fn lock<T>(m: &Mutex<T>) -> MutexGuard<'_, T> {
+    m.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl Shared {
+    fn new(capacity: usize) -> Self {
+        Self {
+            state: Mutex::new(QueueState {
+                items: VecDeque::with_capacity(capacity),
+                closed: false,
+                worker_stopped: false,
+            }),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            capacity,
+            written: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            failed: AtomicU64::new(0),
+            worker_error: Mutex::new(None),
+        }
+    }
+
+    fn push(&self, cmd: Command, policy: BackpressurePolicy) -> PushOutcome {
+        let mut state = lock(&self.state);
+        loop {
+            if state.closed {
+                return PushOutcome::Closed;
+            }
+            if state.worker_stopped {
+                return PushOutcome::WorkerStopped;
+            }
+            if state.items.len() < self.capacity {
+                break;
+            }
+            if cmd.is_message() {
+                match policy {
+                    BackpressurePolicy::DropNewest => {
+                        self.dropped.fetch_add(1, Ordering::Relaxed);
+                        return PushOutcome::Dropped;
+                    }
+                    BackpressurePolicy::DropOldest => {
+                        if let Some(pos) = state.items.iter().position(Command::is_message) {
+                            state.items.remove(pos);
+                            self.dropped.fetch_add(1, Ordering::Relaxed);
+                            break;
+                        }
+                    }
+                    BackpressurePolicy::Block => {}
+                }
+            }
+            state = self.not_full.wait(state).unwrap_or_else(|e| e.into_inner());
+        }
+        state.items.push_back(cmd);
+        self.not_empty.notify_one();
+        PushOutcome::Queued
+    }
+
+    // Blocks until a command is available; returns None once closed and fully drained.
+    fn pop(&self) -> Option<Command> {
+        let mut state = lock(&self.state);
+        loop {
+            if let Some(cmd) = state.items.pop_front() {
+                self.not_full.notify_one();
+                return Some(cmd);
+            }
+            if state.closed {
+                return None;
+            }
+            state = self
+                .not_empty
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    fn close(&self) {
+        lock(&self.state).closed = true;
+        self.not_empty.notify_all();
+        self.not_full.notify_all();
+    }
+
+    // Wakes blocked producers so they can report the stopped worker instead of waiting forever.
+    fn mark_worker_stopped(&self) {
+        lock(&self.state).worker_stopped = true;
+        self.not_full.notify_all();
+    }
+
+    fn depth(&self) -> usize {
+        lock(&self.state).items.len()
+    }
+
+    fn record_error(&self, msg: String) {
+        *lock(&self.worker_error) = Some(msg);
+    }
+
+    fn take_error(&self) -> Option<String> {
+        lock(&self.worker_error).take()
+    }
+}
+
+// Marks the worker as stopped when the writer thread exits, even by unwinding from a panic.
+struct WorkerGuard<'a>(&'a Shared);
+
+impl Drop for WorkerGuard<'_> {
+    fn drop(&mut self) {
+        self.0.mark_worker_stopped();
+    }
+}
+
+// Writer thread body: owns the mcap::Writer and drains the queue until closed.
+fn run_worker(mut writer: Writer<BufWriter<File>>, shared: &Shared) -> Result<(), String> {
+    let _guard = WorkerGuard(shared);
+    while let Some(cmd) = shared.pop() {
+        match cmd {
+            Command::AddSchema {
+                name,
+                encoding,
+                data,
+                reply,
+            } => {
+                let res = writer
+                    .add_schema(&name, &encoding, &data)
+                    .map_err(|e| e.to_string());
+                let _ = reply.send(res);
+            }
+            Command::AddChannel {
+                schema_id,
+                topic,
+                message_encoding,
+                metadata,
+                reply,
+            } => {
+                let res = writer
+                    .add_channel(schema_id, &topic, &message_encoding, &metadata)
+                    .map_err(|e| e.to_string());
+                let _ = reply.send(res);
+            }
+            Command::Message(msg) => match writer.write(&msg) {
+                Ok(()) => {
+                    shared.written.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(e) => {
+                    shared.failed.fetch_add(1, Ordering::Relaxed);
+                    shared.record_error(format!("write failed: {}", e));
+                }
+            },
+            Command::KnownChannelMessage { header, data } => {
+                match writer.write_to_known_channel(&header, &data) {
+                    Ok(()) => {
+                        shared.written.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Err(e) => {
+                        shared.failed.fetch_add(1, Ordering::Relaxed);
+                        shared.record_error(format!("write_to_known_channel failed: {}", e));
+                    }
+                }
+            }
+            Command::Attachment(att) => {
+                if let Err(e) = writer.attach(&att) {
+                    shared.record_error(format!("attach failed: {}", e));
+                }
+            }
+            Command::Metadata(meta) => {
+                if let Err(e) = writer.write_metadata(&meta) {
+                    shared.record_error(format!("write_metadata failed: {}", e));
+                }
+            }
+            Command::Flush => {
+                if let Err(e) = writer.flush() {
+                    shared.record_error(format!("flush failed: {}", e));
+                }
+            }
+        }
+    }
+
+    writer
+        .finish()
+        .map_err(|e| format!("finish failed: {}", e))?;
+    writer
+        .into_inner()
+        .flush()
+        .map_err(|e| format!("flush on close failed: {}", e))
+}
+
+#[derive(GodotClass)]
+/// MCAP writer that performs compression and file I/O on a dedicated background thread.
+///
+/// Overview
+/// - Same record API as `MCAPWriter`, but calls only convert the Resource and enqueue it.
+/// - A worker thread owns the underlying `mcap::Writer`, so chunk compression and disk writes
+///   never run on the calling (main) thread.
+/// - The queue is bounded by `queue_capacity`; `backpressure_policy` decides what happens to
+///   messages when it is full (block, drop the oldest queued message, or drop the new one).
+/// - `close()` drains every queued record, finalizes the summary and joins the worker.
+///
+/// Error handling
+/// - Methods returning `bool` yield `false` when the record could not be queued (not open,
+///   closed, conversion failure, or dropped by the back-pressure policy).
+/// - Errors raised on the worker thread (e.g. writing to an unknown channel) are reported on the
+///   next call and through `get_last_error()`; they also count towards `get_failed_message_count()`.
+/// - `add_schema()` and `add_channel()` wait for the worker to register the record, since the
+///   returned ID is assigned there.
+/// - If the writer thread dies unexpectedly, every further call fails instead of blocking; the
+///   reason is reported by `close()`.
+///
+/// Example
+/// ```gdscript
+/// var w := MCAPAsyncWriter.new()
+/// w.queue_capacity = 4096
+/// w.backpressure_policy = MCAPAsyncWriter.BACKPRESSURE_DROP_OLDEST
+/// if not w.open("user://async.mcap"):
+///     push_error(w.get_last_error())
+///     return
+/// var ch_id := w.add_channel(0, "/pose", "json", {})
+///
+/// # In _process():
+/// var hdr := MCAPMessageHeader.create(ch_id)
+/// w.write_to_known_channel(hdr, payload)
+///
+/// # When done:
+/// w.close()
+/// print("dropped: ", w.get_dropped_message_count())
+/// ```
+///
+/// Notes
+/// - The file is written through the OS (`ProjectSettings.globalize_path()`), since Godot's
+///   `FileAccess` cannot be used off the main thread. Use `user://` or absolute paths.
+/// - Timestamp offsets behave exactly like on `MCAPWriter` and are applied before queuing.
+#[class(init)]
+pub struct MCAPAsyncWriter {
+    base: Base<RefCounted>,
+    path: GString,
+    /// Options for writing the MCAP file. Modify these before calling `open()`.
+    #[export]
+    options: Option<Gd<MCAPWriteOptions>>,
+    /// Maximum number of queued records. Read once in `open()`.
+    #[init(val = 1024)]
+    #[export]
+    queue_capacity: i64,
+    /// What to do with new messages while the queue is full.
+    #[init(val = BackpressurePolicy::Block)]
+    #[export]
+    backpressure_policy: BackpressurePolicy,
+    shared: Option<Arc<Shared>>,
+    worker: Option<JoinHandle<Result<(), String>>>,
+    // Internal last error string
+    last_error: String,
+    // Microsecond offset applied when queuing message/attachment timestamps
+    timestamp_offset_usec: i64,
+    // Once a time-bearing record has been queued the offset can no longer change
+    timestamp_offset_locked: bool,
+    // Time unit of the open file and clock used by `set_timestamp_offset_to_now()` (from `options`)
+    time_unit: MCAPTimeUnit,
+    clock: Clock,
+}
+
+impl MCAPAsyncWriter {
+    fn set_error(&mut self, msg: impl Into<String>) {
+        let s = msg.into();
+        self.last_error = s.clone();
+        godot_error!("{}", s);
+    }
+
+    fn clear_error(&mut self) {
+        self.last_error.clear();
+    }
+
+    // Surface errors raised on the worker thread since the last call.
+    fn poll_worker_error(&mut self) {
+        let pending = self.shared.as_ref().and_then(|s| s.take_error());
+        if let Some(err) = pending {
+            self.set_error(err);
+        }
+    }
+
+    fn adjust_timestamp(&self, value: u64, what: &str) -> Result<u64, String> {
+        to_file_time(self.timestamp_offset_usec, self.time_unit, value, what)
+    }
+
+    fn set_timestamp_offset_internal(&mut self, caller: &str, offset: i64) -> bool {
+        if self.timestamp_offset_locked {
+            self.set_error(format!(
+                "{} called after writing time-bearing records; the timestamp offset is locked",
+                caller
+            ));
+            return false;
+        }
+        self.timestamp_offset_usec = offset;
+        self.clear_error();
+        true
+    }
+
+    fn enqueue(&mut self, caller: &str, cmd: Command) -> bool {
+        let shared = match (&self.worker, &self.shared) {
+            (Some(_), Some(shared)) => shared.clone(),
+            _ => {
+                self.set_error(format!("{} called before open()", caller));
+                return false;
+            }
+        };
+        let time_bearing = cmd.is_time_bearing();
+        match shared.push(cmd, self.backpressure_policy) {
+            PushOutcome::Queued => {
+                if time_bearing {
+                    self.timestamp_offset_locked = true;
+                }
+                true
+            }
+            PushOutcome::Dropped => {
+                self.timestamp_offset_locked = true;
+                false
+            }
+            PushOutcome::Closed => {
+                self.set_error(format!("{} called after the writer was closed", caller));
+                false
+            }
+            PushOutcome::WorkerStopped => {
+                self.set_error(format!(
+                    "{} failed: writer thread stopped unexpectedly; call close()",
+                    caller
+                ));
+                false
+            }
+        }
+    }
+
+    // Queue a registration command and wait for the worker to assign its ID.
+    fn request_id(
+        &mut self,
+        caller: &str,
+        make: impl FnOnce(mpsc::Sender<Result<u16, String>>) -> Command,
+    ) -> i64 {
+        let (tx, rx) = mpsc::channel();
+        if !self.enqueue(caller, make(tx)) {
+            return -1;
+        }
+        match rx.recv() {
+            Ok(Ok(id)) => {
+                self.clear_error();
+                id as i64
+            }
+            Ok(Err(e)) => {
+                self.set_error(format!("{} failed: {}", caller, e));
+                -1
+            }
+            Err(_) => {
+                self.set_error(format!("{} failed: writer thread stopped", caller));
+                -1
+            }
+        }
+    }
+}
+
+#[godot_api]
+impl MCAPAsyncWriter {
+    /// Block until a slot is free (default).
+    #[constant]
+    const BACKPRESSURE_BLOCK: i64 = BackpressurePolicy::Block as i64;
+    /// Evict the oldest queued message to make room.
+    #[constant]
+    const BACKPRESSURE_DROP_OLDEST: i64 = BackpressurePolicy::DropOldest as i64;
+    /// Discard the message being queued.
+    #[constant]
+    const BACKPRESSURE_DROP_NEWEST: i64 = BackpressurePolicy::DropNewest as i64;
+
+    /// Opens the file and starts the writer thread.
+    /// Modify `options`, `queue_capacity` and `backpressure_policy` before calling this.
+    #[func]
+    pub fn open(&mut self, path: GString) -> bool {
+        if self.worker.is_some() {
+            self.set_error("open() called but a file is already open");
+            return false;
+        }
+
+        self.path = path;
+        self.clear_error();
+
+        if self.queue_capacity < 1 {
+            self.set_error("queue_capacity must be at least 1");
+            return false;
+        }
+
+        // 1) open file through the OS; FileAccess is not usable from the worker thread
+        let abs = ProjectSettings::singleton().globalize_path(&self.path);
+        let file = match File::create(abs.to_string()) {
+            Ok(f) => f,
+            Err(err) => {
+                self.set_error(format!("Failed to open {}: {}", self.path, err));
+                return false;
+            }
+        };
+
+        // 2) build MCAP WriteOptions from Resource if provided, else use defaults
+        if self.options.is_none() {
+            self.options = Some(MCAPWriteOptions::new_gd());
+        }
+        let opts = {
+            let o = self.options.as_ref().unwrap().bind();
+            self.time_unit = o.time_unit;
+            self.clock = Clock::from_options(&o);
+            o.to_mcap_owned()
+        };
+
+        // 3) spawn the worker; it creates the mcap::Writer itself and reports back
+        let shared = Arc::new(Shared::new(self.queue_capacity as usize));
+        let worker_shared = shared.clone();
+        let (ready_tx, ready_rx) = mpsc::channel::<Result<(), String>>();
+        let spawned = std::thread::Builder::new()
+            .name("mcap-async-writer".into())
+            .spawn(move || {
+                let writer = match opts.create(BufWriter::new(file)) {
+                    Ok(w) => {
+                        let _ = ready_tx.send(Ok(()));
+                        w
+                    }
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(format!("Failed to create MCAP writer: {}", e)));
+                        return Ok(());
+                    }
+                };
+                run_worker(writer, &worker_shared)
+            });
+        let handle = match spawned {
+            Ok(h) => h,
+            Err(e) => {
+                self.set_error(format!("Failed to spawn writer thread: {}", e));
+                return false;
+            }
+        };
+
+        let ready = ready_rx
+            .recv()
+            .unwrap_or_else(|_| Err("writer thread exited during startup".to_string()));
+        if let Err(e) = ready {
+            let _ = handle.join();
+            self.set_error(e);
+            return false;
+        }
+
+        self.clock.make_active();
+        self.shared = Some(shared);
+        self.worker = Some(handle);
+        self.timestamp_offset_locked = false;
+        true
+    }
+
+    /// Returns whether a file is open and the writer thread is running.
+    #[func]
+    pub fn is_open(&self) -> bool {
+        self.worker.is_some()
+    }
+
+    /// Returns the path of the currently (or last) opened MCAP file.
+    #[func]
+    pub fn get_path(&self) -> GString {
+        self.path.clone()
+    }
+
+    /// Sets the microsecond offset applied to subsequent message and attachment timestamps.
+    /// Same semantics as `MCAPWriter.set_timestamp_offset_usec()`.
+    #[func]
+    pub fn set_timestamp_offset_usec(&mut self, offset: i64) -> bool {
+        self.set_timestamp_offset_internal("set_timestamp_offset_usec", offset)
+    }
+
+    /// Convenience helper that treats the current time of the writer's clock as the zero point for
+    /// future writes. Call after `open()` so the configured `clock_source` is in effect.
+    #[func]
+    pub fn set_timestamp_offset_to_now(&mut self) -> bool {
+        let now = self.clock.now_usec() as i64;
+        self.set_timestamp_offset_internal("set_timestamp_offset_to_now", now)
+    }
+
+    /// Returns the currently configured timestamp offset in microseconds.
+    #[func]
+    pub fn get_timestamp_offset_usec(&self) -> i64 {
+        self.timestamp_offset_usec
+    }
+
+    /// Adds a schema and waits for its ID. Returns -1 on error.
+    #[func]
+    pub fn add_schema(&mut self, name: GString, encoding: GString, data: PackedByteArray) -> i64 {
+        self.poll_worker_error();
+        self.request_id("add_schema", |reply| Command::AddSchema {
+            name: name.to_string(),
+            encoding: encoding.to_string(),
+            data: data.to_vec(),
+            reply,
+        })
+    }
+
+    /// Adds a channel and waits for its ID. Returns -1 on error.
+    ///
+    /// * `schema_id`: a schema_id returned from [`add_schema()`], or 0 if the channel has no schema.
+    #[func]
+    pub fn add_channel(
+        &mut self,
+        schema_id: i32,
+        topic: GString,
+        message_encoding: GString,
+        metadata: Dictionary,
+    ) -> i64 {
+        self.poll_worker_error();
+        let metadata = dict_to_btreemap(&metadata);
+        self.request_id("add_channel", |reply| Command::AddChannel {
+            schema_id: schema_id as u16,
+            topic: topic.to_string(),
+            message_encoding: message_encoding.to_string(),
+            metadata,
+            reply,
+        })
+    }
+
+    /// Queue the given message (and its provided channel, if not already added).
+    /// Returns false if the message could not be queued or was dropped.
+    #[func]
+    pub fn write(&mut self, message: Gd<MCAPMessage>) -> bool {
+        self.poll_worker_error();
+        let mut mcap_msg = match message.bind().to_mcap_owned() {
+            Ok(msg) => msg,
+            Err(err) => {
+                self.set_error(format!(
+                    "write failed to convert MCAPMessage to mcap::Message: {}",
+                    err
+                ));
+                return false;
+            }
+        };
+
+        mcap_msg.log_time = match self.adjust_timestamp(mcap_msg.log_time, "message.log_time") {
+            Ok(t) => t,
+            Err(err) => {
+                self.set_error(err);
+                return false;
+            }
+        };
+        mcap_msg.publish_time =
+            match self.adjust_timestamp(mcap_msg.publish_time, "message.publish_time") {
+                Ok(t) => t,
+                Err(err) => {
+                    self.set_error(err);
+                    return false;
+                }
+            };
+
+        self.enqueue("write", Command::Message(mcap_msg))
+    }
+
+    /// Queue a message for an added channel, given its ID.
+    /// Returns false if the message could not be queued or was dropped.
+    #[func]
+    pub fn write_to_known_channel(
+        &mut self,
+        header: Gd<MCAPMessageHeader>,
+        data: PackedByteArray,
+    ) -> bool {
+        self.poll_worker_error();
+        let mut mcap_header = match header.bind().to_mcap_owned() {
+            Ok(h) => h,
+            Err(err) => {
+                self.set_error(format!("write_to_known_channel failed to convert MCAPMessageHeader to mcap::MessageHeader: {}", err));
+                return false;
+            }
+        };
+
+        mcap_header.log_time = match self.adjust_timestamp(mcap_header.log_time, "header.log_time")
+        {
+            Ok(t) => t,
+            Err(err) => {
+                self.set_error(err);
+                return false;
+            }
+        };
+        mcap_header.publish_time =
+            match self.adjust_timestamp(mcap_header.publish_time, "header.publish_time") {
+                Ok(t) => t,
+                Err(err) => {
+                    self.set_error(err);
+                    return false;
+                }
+            };
+
+        self.enqueue(
+            "write_to_known_channel",
+            Command::KnownChannelMessage {
+                header: mcap_header,
+                data: data.to_vec(),
+            },
+        )
+    }
+
+    /// Queue an attachment. Attachments are never dropped by the back-pressure policy.
+    #[func]
+    pub fn attach(&mut self, attachment: Gd<MCAPAttachment>) -> bool {
+        self.poll_worker_error();
+        let mut mcap_attach = match attachment.bind().to_mcap_owned() {
+            Ok(att) => att,
+            Err(err) => {
+                self.set_error(format!(
+                    "attach failed to convert MCAPAttachment to mcap::Attachment: {}",
+                    err
+                ));
+                return false;
+            }
+        };
+
+        mcap_attach.log_time =
+            match self.adjust_timestamp(mcap_attach.log_time, "attachment.log_time") {
+                Ok(t) => t,
+                Err(err) => {
+                    self.set_error(err);
+                    return false;
+                }
+            };
+        mcap_attach.create_time =
+            match self.adjust_timestamp(mcap_attach.create_time, "attachment.create_time") {
+                Ok(t) => t,
+                Err(err) => {
+                    self.set_error(err);
+                    return false;
+                }
+            };
+
+        self.enqueue("attach", Command::Attachment(mcap_attach))
+    }
+
+    /// Queue a Metadata record. Metadata is never dropped by the back-pressure policy.
+    #[func]
+    pub fn write_metadata(&mut self, metadata: Gd<MCAPMetadata>) -> bool {
+        self.poll_worker_error();
+        let metadata: Metadata = metadata.bind().to_mcap_owned();
+        self.enqueue("write_metadata", Command::Metadata(metadata))
+    }
+
+    /// Ask the worker to finish the current chunk and flush I/O once it reaches this point
+    /// in the queue. Returns immediately.
+    #[func]
+    pub fn flush(&mut self) -> bool {
+        self.poll_worker_error();
+        self.enqueue("flush", Command::Flush)
+    }
+
+    /// Drains the queue, finalizes the file and stops the writer thread. Returns true on success.
+    /// Counters remain readable until the next `open()`.
+    #[func]
+    pub fn close(&mut self) -> bool {
+        let Some(handle) = self.worker.take() else {
+            self.set_error("close called before open()");
+            return false;
+        };
+        if let Some(shared) = &self.shared {
+            shared.close();
+        }
+        let result = handle
+            .join()
+            .unwrap_or_else(|_| Err("writer thread panicked".to_string()));
+        self.timestamp_offset_locked = false;
+        self.poll_worker_error();
+        match result {
+            Ok(()) => true,
+            Err(e) => {
+                self.set_error(e);
+                false
+            }
+        }
+    }
+
+    /// Number of records currently waiting for the writer thread.
+    #[func]
+    pub fn get_queue_depth(&self) -> i64 {
+        self.shared.as_ref().map(|s| s.depth() as i64).unwrap_or(0)
+    }
+
+    /// Number of messages discarded by the back-pressure policy since `open()`.
+    #[func]
+    pub fn get_dropped_message_count(&self) -> i64 {
+        self.shared
+            .as_ref()
+            .map(|s| s.dropped.load(Ordering::Relaxed) as i64)
+            .unwrap_or(0)
+    }
+
+    /// Number of messages written to the file by the worker since `open()`.
+    #[func]
+    pub fn get_written_message_count(&self) -> i64 {
+        self.shared
+            .as_ref()
+            .map(|s| s.written.load(Ordering::Relaxed) as i64)
+            .unwrap_or(0)
+    }
+
+    /// Number of queued messages the worker failed to write since `open()`.
+    #[func]
+    pub fn get_failed_message_count(&self) -> i64 {
+        self.shared
+            .as_ref()
+            .map(|s| s.failed.load(Ordering::Relaxed) as i64)
+            .unwrap_or(0)
+    }
+
+    /// Returns the last encountered error message, or empty string if none.
+    /// Includes errors raised on the writer thread that were not reported yet.
+    #[func]
+    pub fn get_last_error(&self) -> GString {
+        if self.last_error.is_empty()
+            && let Some(shared) = &self.shared
+            && let Some(err) = lock(&shared.worker_error).as_ref()
+        {
+            return GString::from(err.as_str());
+        }
+        GString::from(self.last_error.as_str())
+    }
+}
+
+impl Drop for MCAPAsyncWriter {
+    fn drop(&mut self) {
+        if self.worker.is_some() {
+            godot_print!("MCAPAsyncWriter dropped without calling close(); finalizing file now.");
+            let _ = self.close();
+        }
+    }
+}
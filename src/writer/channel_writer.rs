@@ -0,0 +1,148 @@
+use crate::writer::MCAPWriter;
+use godot::classes::RefCounted;
+use godot::prelude::*;
+use godot::register::property::PhantomVar;
+use mcap::records::MessageHeader;
+
+/// Sequence number and counts of one channel, shared by all of its handles (kept by the writer).
+#[derive(Default)]
+pub(crate) struct ChannelCounters {
+    pub next_sequence: u32,
+    pub message_count: u64,
+    pub byte_count: u64,
+}
+
+#[derive(GodotClass)]
+/// Write handle bound to a single channel of an `MCAPWriter`.
+///
+/// Overview
+/// - Obtained from `MCAPWriter.channel_handle(channel_id)`; keeps the writer alive while referenced.
+/// - `write(payload)` stamps log and publish time from the writer's clock; `write_at(payload, time)`
+///   uses the given time (usec) for both.
+/// - Sequence numbers are assigned per channel, starting at `next_sequence` (0 by default) and
+///   incremented after every successful write. All handles of a channel share the sequence.
+/// - Counts messages and payload bytes written to the channel through any of its handles.
+///
+/// Example
+/// ```gdscript
+/// var w := MCAPWriter.new()
+/// w.open("user://out.mcap")
+/// var pose := w.channel_handle(w.add_channel(0, "/pose", "json", {}))
+/// pose.write(JSON.stringify({"x": 1.0}).to_utf8_buffer())
+/// pose.write_at(JSON.stringify({"x": 2.0}).to_utf8_buffer(), 2_000_000)
+/// print(pose.get_message_count(), " messages, ", pose.get_byte_count(), " bytes")
+/// ```
+///
+/// Notes
+/// - Errors are reported through the parent writer's `get_last_error()`.
+/// - Handles share the writer's timestamp offset; times passed to `write_at()` are shifted like any
+///   other message time.
+#[class(no_init, base=RefCounted)]
+pub struct MCAPChannelWriter {
+    base: Base<RefCounted>,
+    writer: Gd<MCAPWriter>,
+    channel_id: u16,
+    /// Sequence number assigned to the next message written to the channel (shared by its handles).
+    #[var(get = get_next_sequence, set = set_next_sequence)]
+    next_sequence: PhantomVar<i64>,
+}
+
+impl MCAPChannelWriter {
+    pub(crate) fn create(writer: Gd<MCAPWriter>, channel_id: u16) -> Gd<Self> {
+        Gd::from_init_fn(|base| Self {
+            base,
+            writer,
+            channel_id,
+            next_sequence: PhantomVar::default(),
+        })
+    }
+
+    fn write_with_time(&mut self, caller: &str, data: &[u8], time: u64) -> bool {
+        let mut writer = self.writer.bind_mut();
+        let header = MessageHeader {
+            channel_id: self.channel_id,
+            sequence: writer.channel_counters(self.channel_id).next_sequence,
+            log_time: time,
+            publish_time: time,
+        };
+        let ok = writer.write_header_payload(caller, header, data);
+        if ok {
+            let counters = writer.channel_counters(self.channel_id);
+            counters.next_sequence = counters.next_sequence.wrapping_add(1);
+            counters.message_count += 1;
+            counters.byte_count += data.len() as u64;
+        }
+        ok
+    }
+}
+
+#[godot_api]
+impl MCAPChannelWriter {
+    /// Write `payload` stamped with the writer's current clock time.
+    #[func]
+    pub fn write(&mut self, payload: PackedByteArray) -> bool {
+        let now = self.writer.bind().now_usec();
+        self.write_with_time("MCAPChannelWriter.write", payload.as_slice(), now)
+    }
+
+    /// Write `payload` with the given log/publish time in microseconds.
+    #[func]
+    pub fn write_at(&mut self, payload: PackedByteArray, time_usec: i64) -> bool {
+        if time_usec < 0 {
+            self.writer
+                .bind_mut()
+                .set_error("MCAPChannelWriter.write_at called with a negative time");
+            return false;
+        }
+        self.write_with_time(
+            "MCAPChannelWriter.write_at",
+            payload.as_slice(),
+            time_usec as u64,
+        )
+    }
+
+    /// ID of the channel this handle writes to.
+    #[func]
+    pub fn get_channel_id(&self) -> i64 {
+        self.channel_id as i64
+    }
+
+    /// The writer this handle writes into.
+    #[func]
+    pub fn get_writer(&self) -> Gd<MCAPWriter> {
+        self.writer.clone()
+    }
+
+    /// Sequence number assigned to the next message written to the channel.
+    #[func]
+    pub fn get_next_sequence(&self) -> i64 {
+        let mut writer = self.writer.clone();
+        let mut writer = writer.bind_mut();
+        writer.channel_counters(self.channel_id).next_sequence as i64
+    }
+
+    /// Set the sequence number of the next message written to the channel.
+    #[func]
+    pub fn set_next_sequence(&mut self, sequence: i64) {
+        self.writer
+            .bind_mut()
+            .channel_counters(self.channel_id)
+            .next_sequence = sequence as u32;
+    }
+
+    /// Number of messages successfully written to the channel through its handles.
+    #[func]
+    pub fn get_message_count(&self) -> i64 {
+        let mut writer = self.writer.clone();
+        let mut writer = writer.bind_mut();
+        writer.channel_counters(self.channel_id).message_count as i64
+    }
+
+    /// Number of payload bytes successfully written to the channel through its handles.
+    #[func]
+    pub fn get_byte_count(&self) -> i64 {
+        let mut writer = self.writer.clone();
+        let mut writer = writer.bind_mut();
+        writer.channel_counters(self.channel_id).byte_count as i64
+    }
+}
@@ -1,17 +1,29 @@
+use crate::reader::MCAPReader;
+use crate::time::Clock;
+use crate::writer::MCAPChannelWriter;
+use crate::writer::channel_writer::ChannelCounters;
+use crate::writer::sink::{SinkTarget, WriterSink};
+use crate::writer::summary::{MergedSummary, SummaryOptions};
 use crate::{types::*, util::*};
 use enumset::EnumSet;
-use godot::classes::{RefCounted, Time, file_access::ModeFlags};
+use godot::classes::{FileAccess, ProjectSettings, RefCounted, file_access::ModeFlags};
 use godot::prelude::*;
 use godot::tools::GFile;
-use mcap::Writer;
-use mcap::records::Metadata;
+use mcap::records::{MessageHeader, Metadata};
 use mcap::write::PrivateRecordOptions;
+use mcap::{Channel, Schema, Summary, Writer};
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::io::{Seek, SeekFrom};
+use std::rc::Rc;
+use std::sync::Arc;
 
 #[derive(GodotClass)]
 /// MCAP file writer for Godot.
 ///
 /// Overview
 /// - Opens a file and writes MCAP records (channels, schemas, messages, attachments, metadata).
+/// - Alternatively writes into memory via `open_in_memory()`; `close_to_bytes()` returns the finished MCAP.
 /// - Accepts either full `MCAPMessage` resources via `write()` or pairs of header+payload via `write_to_known_channel()`.
 /// - Exposes a configurable `options` Resource to control chunking, compression, and emitted indexes before opening.
 ///
@@ -90,25 +102,76 @@ use mcap::write::PrivateRecordOptions;
 /// - `flush()` finishes the current chunk and flushes I/O to keep the file streamable mid-session.
 /// - Timestamps are microseconds (usec). Configure `set_timestamp_offset_*()` if you need to
 ///   shift the stored timebase for messages created with engine-relative clocks.
+/// - `options.time_unit` selects the unit stored in the file (e.g. nanoseconds for Foxglove/ROS 2);
+///   times passed in are still microseconds and are converted after the offset is applied.
 #[class(init)]
-struct MCAPWriter {
+pub struct MCAPWriter {
     base: Base<RefCounted>,
     path: GString,
-    writer: Option<Writer<GFile>>,
+    writer: Option<Writer<WriterSink>>,
+    // Size of the current file in bytes, updated by the sink as data reaches it
+    bytes_written: Rc<Cell<u64>>,
     /// Options for writing the MCAP file. Modify these before calling `open()`.
     #[export]
-    options: Option<Gd<MCAPWriteOptions>>,
+    pub(crate) options: Option<Gd<MCAPWriteOptions>>,
     // Internal last error string
     last_error: String,
     // Microsecond offset applied when writing message/attachment timestamps
     timestamp_offset_usec: i64,
     // Once a time-bearing record has been written the offset can no longer change
     timestamp_offset_locked: bool,
+    // Whether the current session writes to memory (see `open_in_memory()`)
+    in_memory: bool,
+    // Finished MCAP of an in-memory session, held until `take_bytes()`
+    memory_result: Option<Vec<u8>>,
+    // Time unit of the open file and clock used for self-stamped messages (from `options`)
+    time_unit: MCAPTimeUnit,
+    clock: Clock,
+    // Summary written on close in place of the mcap writer's own: set for append sessions and
+    // for files that start with reserved IDs (see `open_registered()`)
+    merged: Option<MergedSummary>,
+    // Sequence numbers and counts of channel handles, per channel of the current file
+    channel_counters: HashMap<u16, ChannelCounters>,
+}
+
+/// Shift `value` by a microsecond offset (positive offsets move timestamps toward zero).
+/// Shared by all writer front-ends so they reject underflow/overflow identically.
+pub(super) fn apply_timestamp_offset(offset: i64, value: u64, what: &str) -> Result<u64, String> {
+    if offset == 0 {
+        return Ok(value);
+    }
+
+    if offset > 0 {
+        let offset = offset as u64;
+        if value < offset {
+            return Err(format!(
+                "{what} ({value}) is earlier than the configured timestamp offset ({offset})"
+            ));
+        }
+        Ok(value - offset)
+    } else {
+        let offset = offset.unsigned_abs();
+        value
+            .checked_add(offset)
+            .ok_or_else(|| format!("{what} overflowed when applying the timestamp offset"))
+    }
+}
+
+/// Shift a microsecond `value` by the offset and encode it in the file's time unit.
+pub(super) fn to_file_time(
+    offset: i64,
+    unit: MCAPTimeUnit,
+    value: u64,
+    what: &str,
+) -> Result<u64, String> {
+    let shifted = apply_timestamp_offset(offset, value, what)?;
+    unit.usec_to_unit(shifted)
+        .map_err(|e| format!("{what}: {e}"))
 }
 
 impl MCAPWriter {
     /// Set and log the last error.
-    fn set_error(&mut self, msg: impl Into<String>) {
+    pub(crate) fn set_error(&mut self, msg: impl Into<String>) {
         let s = msg.into();
         self.last_error = s.clone();
         godot_error!("{}", s);
@@ -120,7 +183,7 @@ impl MCAPWriter {
     }
 
     /// Get a mutable reference to the writer or set an error if it's not open.
-    fn writer_or_err_mut(&mut self, caller: &str) -> Option<&mut Writer<GFile>> {
+    fn writer_or_err_mut(&mut self, caller: &str) -> Option<&mut Writer<WriterSink>> {
         if self.writer.is_none() {
             self.set_error(format!("{} called before open()", caller));
             return None;
@@ -132,7 +195,7 @@ impl MCAPWriter {
     fn with_writer<R, E>(
         &mut self,
         caller: &str,
-        f: impl FnOnce(&mut Writer<GFile>) -> Result<R, E>,
+        f: impl FnOnce(&mut Writer<WriterSink>) -> Result<R, E>,
         err_ret: R,
     ) -> R
     where
@@ -176,28 +239,156 @@ impl MCAPWriter {
     }
 
     fn adjust_timestamp(&self, value: u64, what: &str) -> Result<u64, String> {
-        if self.timestamp_offset_usec == 0 {
-            return Ok(value);
+        to_file_time(self.timestamp_offset_usec, self.time_unit, value, what)
+    }
+
+    fn lock_timestamp_offset(&mut self) {
+        self.timestamp_offset_locked = true;
+    }
+
+    /// Apply the timestamp offset and write a header+payload pair to a known channel.
+    /// Shared by `write_to_known_channel()` and `MCAPChannelWriter`.
+    pub(crate) fn write_header_payload(
+        &mut self,
+        caller: &str,
+        mut header: MessageHeader,
+        data: &[u8],
+    ) -> bool {
+        header.log_time = match self.adjust_timestamp(header.log_time, "header.log_time") {
+            Ok(t) => t,
+            Err(err) => {
+                self.set_error(err);
+                return false;
+            }
+        };
+        header.publish_time =
+            match self.adjust_timestamp(header.publish_time, "header.publish_time") {
+                Ok(t) => t,
+                Err(err) => {
+                    self.set_error(err);
+                    return false;
+                }
+            };
+
+        let ok = self.with_writer(
+            caller,
+            |w| w.write_to_known_channel(&header, data).map(|_| true),
+            false,
+        );
+
+        if ok {
+            self.lock_timestamp_offset();
+            if let Some(merged) = self.merged.as_mut() {
+                merged.note_message(header.log_time);
+            }
         }
 
-        if self.timestamp_offset_usec > 0 {
-            let offset = self.timestamp_offset_usec as u64;
-            if value < offset {
-                return Err(format!(
-                    "{what} ({value}) is earlier than the configured timestamp offset ({offset})"
-                ));
+        ok
+    }
+
+    /// Sequence number and counts shared by the handles of `channel_id`.
+    pub(crate) fn channel_counters(&mut self, channel_id: u16) -> &mut ChannelCounters {
+        self.channel_counters.entry(channel_id).or_default()
+    }
+
+    /// Current time of the writer's clock in microseconds (see `MCAPWriteOptions.clock_source`).
+    pub(crate) fn now_usec(&self) -> u64 {
+        self.clock.now_usec()
+    }
+
+    /// Build the mcap::Writer on top of `sink` using the configured options.
+    /// With an `existing` summary, the writer continues that file (see `open_append()`) or starts
+    /// one whose IDs are reserved up front (see `open_registered()`).
+    fn create_writer(&mut self, sink: WriterSink, existing: Option<Summary>) -> bool {
+        // build MCAP WriteOptions from Resource if provided, else use defaults
+        if self.options.is_none() {
+            let default_opts = MCAPWriteOptions::new_gd();
+            self.options = Some(default_opts);
+        }
+        let (mut opts, time_unit, clock) = {
+            let o = self.options.as_ref().unwrap().bind();
+            (o.to_mcap_owned(), o.time_unit, Clock::from_options(&o))
+        };
+        self.time_unit = time_unit;
+        self.clock = clock;
+        self.merged = existing.map(|summary| {
+            let options = SummaryOptions::new(&self.options.as_ref().unwrap().bind());
+            MergedSummary::new(summary, options, &sink)
+        });
+        if self.merged.is_some() {
+            opts = MergedSummary::writer_options(opts);
+        }
+
+        match opts.create(sink) {
+            Ok(w) => {
+                self.writer = Some(w);
+                self.channel_counters.clear();
+                self.clock.make_active();
+                self.timestamp_offset_locked = false;
+                self.clear_error();
+                true
+            }
+            Err(e) => {
+                self.set_error(format!("Failed to create MCAP writer: {}", e));
+                self.writer = None;
+                self.merged = None;
+                false
             }
-            Ok(value - offset)
-        } else {
-            let offset = (-self.timestamp_offset_usec) as u64;
-            value
-                .checked_add(offset)
-                .ok_or_else(|| format!("{what} overflowed when applying the timestamp offset"))
         }
     }
 
-    fn lock_timestamp_offset(&mut self) {
-        self.timestamp_offset_locked = true;
+    /// Register `channels` and `schemas` under their own IDs with the writer just created for a
+    /// merged summary. `in_file` tells whether their records are already in the file.
+    fn reserve_ids(
+        &mut self,
+        caller: &str,
+        channels: &[Arc<Channel<'static>>],
+        schemas: &[Arc<Schema<'static>>],
+        in_file: bool,
+    ) -> bool {
+        let (Some(mut w), Some(mut merged)) = (self.writer.take(), self.merged.take()) else {
+            unreachable!("create_writer() succeeded");
+        };
+        if let Err(err) = merged.reserve_ids(&mut w, channels, schemas, &HashSet::new(), in_file) {
+            merged.abandon(w);
+            self.set_error(format!(
+                "{} failed to register the channels of {}: {}",
+                caller, self.path, err
+            ));
+            return false;
+        }
+        self.writer = Some(w);
+        self.merged = Some(merged);
+        true
+    }
+
+    /// Like `open()`, but registers `channels` and `schemas` under their own IDs before anything
+    /// else is written, so IDs handed out by an earlier file stay valid (see
+    /// `MCAPRotatingWriter`). Schemas without any channel occupy channel IDs counting down from
+    /// 65535 for the session.
+    pub(crate) fn open_registered(
+        &mut self,
+        path: GString,
+        channels: &[Arc<Channel<'static>>],
+        schemas: &[Arc<Schema<'static>>],
+    ) -> bool {
+        if self.writer.is_some() {
+            self.set_error("open() called but a file is already open");
+            return false;
+        }
+        self.path = path;
+        self.clear_error();
+        let file = match GFile::open(&self.path, ModeFlags::WRITE) {
+            Ok(f) => f,
+            Err(err) => {
+                self.set_error(format!("Failed to open {}: {}", self.path, err));
+                return false;
+            }
+        };
+        self.in_memory = false;
+        let sink = WriterSink::new(file, self.bytes_written.clone());
+        self.create_writer(sink, Some(Summary::default()))
+            && self.reserve_ids("open", channels, schemas, false)
     }
 }
 
@@ -227,27 +418,96 @@ impl MCAPWriter {
             }
         };
 
-        // 2) build MCAP WriteOptions from Resource if provided, else use defaults
-        if self.options.is_none() {
-            let default_opts = MCAPWriteOptions::new_gd();
-            self.options = Some(default_opts);
+        self.in_memory = false;
+        let sink = WriterSink::new(file, self.bytes_written.clone());
+        self.create_writer(sink, None)
+    }
+
+    /// Starts a new MCAP in an in-memory buffer instead of a file.
+    /// Use `close_to_bytes()` (or `close()` followed by `take_bytes()`) to get the finished MCAP.
+    #[func]
+    pub fn open_in_memory(&mut self) -> bool {
+        if self.writer.is_some() {
+            self.set_error("open_in_memory() called but a file is already open");
+            return false;
         }
-        let opts = self.options.as_ref().unwrap().bind().to_mcap_owned();
 
-        // 3) create writer with options
-        match opts.create(file) {
-            Ok(w) => {
-                self.writer = Some(w);
-                self.timestamp_offset_locked = false;
-                self.clear_error();
-                true
+        self.path = GString::new();
+        self.in_memory = true;
+        self.memory_result = None;
+        self.clear_error();
+
+        let sink = WriterSink::in_memory(self.bytes_written.clone());
+        self.create_writer(sink, None)
+    }
+
+    /// Reopens an existing MCAP file and continues writing after its last record.
+    ///
+    /// The file is truncated after its data section (dropping the old summary, and any damaged or
+    /// incomplete tail of a file that was never finalized) and new records are written in place.
+    /// Schemas and channels of the existing file keep their IDs and can be written to right away;
+    /// `close()` writes a summary covering old and new records. If `path` does not exist, this
+    /// behaves like `open()`.
+    ///
+    /// Until `close()` the file has no summary; after a crash, `MCAPReader.recover()` repairs it.
+    /// Schemas without any channel occupy channel IDs counting down from 65535 for the session.
+    /// Keep `options.time_unit` equal to the unit of the existing file, since existing timestamps
+    /// stay as they are.
+    #[func]
+    pub fn open_append(&mut self, path: GString) -> bool {
+        if self.writer.is_some() {
+            self.set_error("open_append() called but a file is already open");
+            return false;
+        }
+        if !FileAccess::file_exists(&path) {
+            return self.open(path);
+        }
+
+        self.clear_error();
+        let point = match MCAPReader::append_point(&path) {
+            Ok(p) => p,
+            Err(err) => {
+                self.set_error(format!("open_append failed to read {}: {}", path, err));
+                return false;
             }
-            Err(e) => {
-                self.set_error(format!("Failed to create MCAP writer: {}", e));
-                self.writer = None;
-                false
+        };
+        if let Some(damage) = &point.damage {
+            godot_warn!("open_append: dropping the end of {}: {}", path, damage);
+        }
+
+        let os_path = ProjectSettings::singleton()
+            .globalize_path(&path)
+            .to_string();
+        let truncated = std::fs::OpenOptions::new()
+            .write(true)
+            .open(&os_path)
+            .and_then(|f| f.set_len(point.data_end));
+        if let Err(err) = truncated {
+            self.set_error(format!("Failed to truncate {}: {}", path, err));
+            return false;
+        }
+        let file = GFile::open(&path, ModeFlags::READ_WRITE).and_then(|mut f| {
+            f.seek(SeekFrom::End(0))?;
+            Ok(f)
+        });
+        let file = match file {
+            Ok(f) => f,
+            Err(err) => {
+                self.set_error(format!("Failed to open {}: {}", path, err));
+                return false;
             }
+        };
+
+        self.path = path;
+        self.in_memory = false;
+        let sink = WriterSink::appending(file, point.data_end, self.bytes_written.clone());
+        let summary = point.summary;
+        let channels: Vec<_> = summary.channels.values().cloned().collect();
+        let schemas: Vec<_> = summary.schemas.values().cloned().collect();
+        if !self.create_writer(sink, Some(summary)) {
+            return false;
         }
+        self.reserve_ids("open_append", &channels, &schemas, true)
     }
 
     /// Returns whether the MCAPWriter is currently open.
@@ -264,6 +524,13 @@ impl MCAPWriter {
         self.path.clone()
     }
 
+    /// Returns the number of bytes written to the current file so far.
+    /// Chunked messages only count once their chunk has been finished (see `flush()`).
+    #[func]
+    pub fn get_bytes_written(&self) -> i64 {
+        self.bytes_written.get() as i64
+    }
+
     /// Sets the microsecond offset that will be applied to subsequent message and attachment timestamps.
     /// Positive offsets shift timestamps backwards (toward zero); negative offsets shift them forward.
     /// The offset can be changed freely until a time-bearing record is written, after which it locks.
@@ -273,10 +540,11 @@ impl MCAPWriter {
         self.set_timestamp_offset_internal("set_timestamp_offset_usec", offset)
     }
 
-    /// Convenience helper that treats the current engine ticks as the zero point for future writes.
+    /// Convenience helper that treats the current time of the writer's clock as the zero point for
+    /// future writes. Call after `open()` so the configured `clock_source` is in effect.
     #[func]
     pub fn set_timestamp_offset_to_now(&mut self) -> bool {
-        let now = Time::singleton().get_ticks_usec() as i64;
+        let now = self.now_usec() as i64;
         self.set_timestamp_offset_internal("set_timestamp_offset_to_now", now)
     }
 
@@ -286,6 +554,13 @@ impl MCAPWriter {
         self.timestamp_offset_usec
     }
 
+    /// Returns the current time of the writer's clock in microseconds.
+    /// Useful for stamping `MCAPMessage`/`MCAPMessageHeader` resources consistently with channel handles.
+    #[func]
+    pub fn get_clock_time_usec(&self) -> i64 {
+        self.now_usec() as i64
+    }
+
     /// Adds a schema, returning its ID. If a schema with the same content has been added already,
     /// its ID is returned. Returns -1 on error.
     ///
@@ -398,6 +673,15 @@ impl MCAPWriter {
         ch.id = new_id;
     }
 
+    /// Returns a handle for writing payloads to an added channel without building headers.
+    /// The handle numbers messages and stamps them with the writer's clock; see `MCAPChannelWriter`.
+    /// All handles of a channel share its sequence numbers and counts, which start over with every
+    /// file. The channel ID is validated when the first message is written.
+    #[func]
+    pub fn channel_handle(&self, channel_id: i32) -> Gd<MCAPChannelWriter> {
+        MCAPChannelWriter::create(self.to_gd(), channel_id as u16)
+    }
+
     /// Write the given message (and its provided channel, if not already added).
     /// The provided channel ID and schema ID will be used as IDs in the resulting MCAP.
     /// The writer applies its configured timestamp offset before serializing the record.
@@ -433,6 +717,9 @@ impl MCAPWriter {
         let ok = self.with_writer("write", |w| w.write(&mcap_msg).map(|_| true), false);
         if ok {
             self.lock_timestamp_offset();
+            if let Some(merged) = self.merged.as_mut() {
+                merged.note_message(mcap_msg.log_time);
+            }
         }
         ok
     }
@@ -447,7 +734,7 @@ impl MCAPWriter {
         header: Gd<MCAPMessageHeader>,
         data: PackedByteArray,
     ) -> bool {
-        let mut mcap_header = match header.bind().to_mcap_owned() {
+        let mcap_header = match header.bind().to_mcap_owned() {
             Ok(h) => h,
             Err(err) => {
                 self.set_error(format!("write_to_known_channel failed to convert MCAPMessageHeader to mcap::MessageHeader: {}", err));
@@ -455,37 +742,7 @@ impl MCAPWriter {
             }
         };
 
-        mcap_header.log_time = match self.adjust_timestamp(mcap_header.log_time, "header.log_time")
-        {
-            Ok(t) => t,
-            Err(err) => {
-                self.set_error(err);
-                return false;
-            }
-        };
-        mcap_header.publish_time =
-            match self.adjust_timestamp(mcap_header.publish_time, "header.publish_time") {
-                Ok(t) => t,
-                Err(err) => {
-                    self.set_error(err);
-                    return false;
-                }
-            };
-
-        let ok = self.with_writer(
-            "write_to_known_channel",
-            |w| {
-                w.write_to_known_channel(&mcap_header, data.as_slice())
-                    .map(|_| true)
-            },
-            false,
-        );
-
-        if ok {
-            self.lock_timestamp_offset();
-        }
-
-        ok
+        self.write_header_payload("write_to_known_channel", mcap_header, data.as_slice())
     }
 
     /// Write a private record using the provided options.
@@ -596,15 +853,24 @@ impl MCAPWriter {
     #[func]
     pub fn close(&mut self) -> bool {
         if let Some(mut w) = self.writer.take() {
-            match w.finish() {
-                Ok(_summary) => {
+            let finished = match self.merged.take() {
+                Some(merged) => merged.finish(w),
+                None => w
+                    .finish()
+                    .map(|_summary| w.into_inner())
+                    .map_err(|e| e.to_string()),
+            };
+            self.timestamp_offset_locked = false;
+            match finished {
+                Ok(sink) => {
+                    if let SinkTarget::Memory(buf) = sink.into_target() {
+                        self.memory_result = Some(buf.into_inner());
+                    }
                     self.clear_error();
-                    self.timestamp_offset_locked = false;
                     true
                 }
                 Err(e) => {
                     self.set_error(format!("finish failed: {}", e));
-                    self.timestamp_offset_locked = false;
                     false
                 }
             }
@@ -614,6 +880,30 @@ impl MCAPWriter {
         }
     }
 
+    /// Returns the bytes of an in-memory MCAP finalized by `close()`, and releases them.
+    /// Returns an empty array if no in-memory MCAP is pending.
+    #[func]
+    pub fn take_bytes(&mut self) -> PackedByteArray {
+        match self.memory_result.take() {
+            Some(bytes) => PackedByteArray::from(bytes),
+            None => PackedByteArray::new(),
+        }
+    }
+
+    /// Finalizes an in-memory MCAP started with `open_in_memory()` and returns its bytes.
+    /// Returns an empty array on error (see `get_last_error()`).
+    #[func]
+    pub fn close_to_bytes(&mut self) -> PackedByteArray {
+        if self.writer.is_some() && !self.in_memory {
+            self.set_error("close_to_bytes() called on a file-backed writer; use close()");
+            return PackedByteArray::new();
+        }
+        if !self.close() {
+            return PackedByteArray::new();
+        }
+        self.take_bytes()
+    }
+
     /// Returns the last encountered error message, or empty string if none.
     #[func]
     pub fn get_last_error(&self) -> GString {
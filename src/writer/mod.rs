@@ -0,0 +1,20 @@
+mod async_writer;
+mod channel_writer;
+mod mcap_writer;
+mod recorder;
+mod rotating_writer;
+mod sink;
+mod summary;
+
+#[allow(unused_imports)]
+pub use async_writer::{BackpressurePolicy, MCAPAsyncWriter};
+#[allow(unused_imports)]
+pub use channel_writer::MCAPChannelWriter;
+#[allow(unused_imports)]
+pub use mcap_writer::MCAPWriter;
+#[allow(unused_imports)]
+pub use recorder::MCAPRingRecorder;
+#[allow(unused_imports)]
+pub use rotating_writer::MCAPRotatingWriter;
+pub(crate) use sink::{SinkTarget, WriterSink};
+pub(crate) use summary::{MergedSummary, SummaryOptions};
@@ -0,0 +1,469 @@
+use crate::{types::*, util::*};
+use godot::classes::{RefCounted, file_access::ModeFlags};
+use godot::prelude::*;
+use godot::tools::GFile;
+use mcap::records::{MessageHeader, Metadata};
+use mcap::{Attachment, McapError, Message, Writer};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io::{Cursor, Seek, Write};
+
+struct SchemaEntry {
+    name: String,
+    encoding: String,
+    data: Vec<u8>,
+}
+
+struct ChannelEntry {
+    schema_id: u16,
+    topic: String,
+    message_encoding: String,
+    metadata: BTreeMap<String, String>,
+}
+
+// Borrowed channel content (schema ID, topic, message encoding, metadata) used to detect ID clashes.
+type ChannelContent<'a> = (u16, &'a str, &'a str, &'a BTreeMap<String, String>);
+
+// A buffered time-bearing record, kept in arrival order.
+enum Buffered {
+    Message(Message<'static>),
+    KnownMessage(MessageHeader, Vec<u8>),
+    Attachment(Attachment<'static>),
+}
+
+impl Buffered {
+    fn log_time(&self) -> u64 {
+        match self {
+            Buffered::Message(m) => m.log_time,
+            Buffered::KnownMessage(h, _) => h.log_time,
+            Buffered::Attachment(a) => a.log_time,
+        }
+    }
+
+    fn payload_len(&self) -> usize {
+        match self {
+            Buffered::Message(m) => m.data.len(),
+            Buffered::KnownMessage(_, d) => d.len(),
+            Buffered::Attachment(a) => a.data.len(),
+        }
+    }
+
+    fn is_message(&self) -> bool {
+        !matches!(self, Buffered::Attachment(_))
+    }
+}
+
+#[derive(GodotClass)]
+/// In-memory "black box" recorder that keeps only the most recent data.
+///
+/// Overview
+/// - Accepts the same inputs as `MCAPWriter` (schemas, channels, `MCAPMessage`, header+payload,
+///   attachments, metadata) but only buffers them in memory.
+/// - Old messages and attachments are evicted once they fall outside `window_usec` (measured from
+///   the newest log time seen) or once the buffered payload exceeds `max_bytes`.
+/// - `dump(path)` / `dump_to_bytes()` write the current buffer as a complete, indexed MCAP using
+///   `options`. Dumping does not clear the buffer; recording can continue afterwards.
+///
+/// Example
+/// ```gdscript
+/// var rec := MCAPRingRecorder.new()
+/// rec.window_usec = 30 * 1_000_000 # keep the last 30 seconds
+/// var ch_id := rec.add_channel(0, "/player", "json", {})
+///
+/// func _physics_process(_dt):
+///     var hdr := MCAPMessageHeader.create(ch_id)
+///     rec.write_to_known_channel(hdr, JSON.stringify(state).to_utf8_buffer())
+///
+/// func _on_bug_reported():
+///     if not rec.dump("user://bug_%d.mcap" % Time.get_unix_time_from_system()):
+///         push_error(rec.get_last_error())
+/// ```
+///
+/// Notes
+/// - Schemas, channels and metadata are never evicted and are written at the start of every dump.
+/// - Eviction happens in arrival order: records are dropped from the front of the buffer while they
+///   are older than the window, so a late out-of-order record may outlive the window briefly.
+/// - `max_bytes` counts payload bytes (message data and attachment data) only.
+/// - Timestamps are microseconds (usec); they are converted to `options.time_unit` when dumping.
+/// - A message passed to `write()` whose embedded channel or schema reuses an ID already taken by
+///   different content (e.g. one handed out by `add_channel()`) is skipped when dumping; see
+///   `get_skipped_message_count()`.
+#[class(init)]
+pub struct MCAPRingRecorder {
+    base: Base<RefCounted>,
+    /// Options used when dumping. Modify these before calling `dump()`.
+    #[export]
+    options: Option<Gd<MCAPWriteOptions>>,
+    /// Keep records whose log time is within this many usec of the newest one (0 = no time limit).
+    #[export]
+    #[init(val = 10_000_000)]
+    window_usec: i64,
+    /// Evict the oldest records once the buffered payload exceeds this many bytes (0 = no size limit).
+    #[export]
+    max_bytes: i64,
+    schemas: Vec<SchemaEntry>,
+    channels: Vec<ChannelEntry>,
+    metadata: Vec<Metadata>,
+    records: VecDeque<Buffered>,
+    buffered_bytes: usize,
+    newest_log_time: u64,
+    evicted_messages: u64,
+    skipped_messages: u64,
+    last_error: String,
+}
+
+impl MCAPRingRecorder {
+    fn set_error(&mut self, msg: impl Into<String>) {
+        let s = msg.into();
+        self.last_error = s.clone();
+        godot_error!("{}", s);
+    }
+
+    fn clear_error(&mut self) {
+        self.last_error.clear();
+    }
+
+    fn push(&mut self, record: Buffered) {
+        self.newest_log_time = self.newest_log_time.max(record.log_time());
+        self.buffered_bytes += record.payload_len();
+        self.records.push_back(record);
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        let cutoff = if self.window_usec > 0 {
+            Some(self.newest_log_time.saturating_sub(self.window_usec as u64))
+        } else {
+            None
+        };
+        while let Some(front) = self.records.front() {
+            let too_old = cutoff.is_some_and(|c| front.log_time() < c);
+            let too_big = self.max_bytes > 0 && self.buffered_bytes > self.max_bytes as usize;
+            if !too_old && !too_big {
+                break;
+            }
+            let front = self.records.pop_front().unwrap();
+            self.buffered_bytes -= front.payload_len();
+            if front.is_message() {
+                self.evicted_messages += 1;
+            }
+        }
+    }
+
+    // Replay the buffer into a fresh writer; registrations first so IDs match the ones handed out.
+    fn dump_into<W: Write + Seek>(&mut self, sink: W) -> Result<W, McapError> {
+        if self.options.is_none() {
+            self.options = Some(MCAPWriteOptions::new_gd());
+        }
+        let (opts, unit) = {
+            let o = self.options.as_ref().unwrap().bind();
+            (o.to_mcap_owned(), o.time_unit)
+        };
+        // Buffered times are usec; encode them in the file's unit on the way out
+        let conv = |t: u64| unit.usec_to_unit_saturating(t);
+        let mut w: Writer<W> = opts.create(sink)?;
+        // Content registered under each ID, so embedded channels/schemas that clash can be skipped
+        let mut schema_ids: HashMap<u16, (&str, &str, &[u8])> = HashMap::new();
+        let mut channel_ids: HashMap<u16, ChannelContent> = HashMap::new();
+        for s in &self.schemas {
+            let id = w.add_schema(&s.name, &s.encoding, &s.data)?;
+            schema_ids.insert(id, (&s.name, &s.encoding, &s.data));
+        }
+        for c in &self.channels {
+            let id = w.add_channel(c.schema_id, &c.topic, &c.message_encoding, &c.metadata)?;
+            channel_ids.insert(
+                id,
+                (c.schema_id, &c.topic, &c.message_encoding, &c.metadata),
+            );
+        }
+        for m in &self.metadata {
+            w.write_metadata(m)?;
+        }
+        let mut skipped = 0;
+        for r in &self.records {
+            match r {
+                Buffered::Message(m) => {
+                    let schema = m
+                        .channel
+                        .schema
+                        .as_ref()
+                        .map(|s| (s.id, (&s.name[..], &s.encoding[..], &s.data[..])));
+                    let channel = (
+                        schema.map_or(0, |(id, _)| id),
+                        &m.channel.topic[..],
+                        &m.channel.message_encoding[..],
+                        &m.channel.metadata,
+                    );
+                    let clash = schema.is_some_and(|(id, content)| {
+                        schema_ids.get(&id).is_some_and(|known| *known != content)
+                    }) || channel_ids
+                        .get(&m.channel.id)
+                        .is_some_and(|known| *known != channel);
+                    if clash {
+                        skipped += 1;
+                        continue;
+                    }
+                    if let Some((id, content)) = schema {
+                        schema_ids.insert(id, content);
+                    }
+                    channel_ids.insert(m.channel.id, channel);
+                    w.write(&Message {
+                        channel: m.channel.clone(),
+                        sequence: m.sequence,
+                        log_time: conv(m.log_time),
+                        publish_time: conv(m.publish_time),
+                        data: Cow::Borrowed(&m.data[..]),
+                    })?
+                }
+                Buffered::KnownMessage(h, d) => {
+                    let header = MessageHeader {
+                        log_time: conv(h.log_time),
+                        publish_time: conv(h.publish_time),
+                        ..*h
+                    };
+                    w.write_to_known_channel(&header, d)?
+                }
+                Buffered::Attachment(a) => w.attach(&Attachment {
+                    log_time: conv(a.log_time),
+                    create_time: conv(a.create_time),
+                    name: a.name.clone(),
+                    media_type: a.media_type.clone(),
+                    data: Cow::Borrowed(&a.data[..]),
+                })?,
+            }
+        }
+        w.finish()?;
+        self.skipped_messages = skipped;
+        if skipped > 0 {
+            godot_warn!(
+                "dump skipped {} message(s) whose channel or schema ID was already taken by different content",
+                skipped
+            );
+        }
+        Ok(w.into_inner())
+    }
+}
+
+#[godot_api]
+impl MCAPRingRecorder {
+    /// Adds a schema, returning its ID. If a schema with the same content has been added already,
+    /// its ID is returned. IDs are assigned exactly like `MCAPWriter.add_schema()`.
+    #[func]
+    pub fn add_schema(&mut self, name: GString, encoding: GString, data: PackedByteArray) -> i64 {
+        let entry = SchemaEntry {
+            name: name.to_string(),
+            encoding: encoding.to_string(),
+            data: data.to_vec(),
+        };
+        let existing = self.schemas.iter().position(|s| {
+            s.name == entry.name && s.encoding == entry.encoding && s.data == entry.data
+        });
+        let index = existing.unwrap_or_else(|| {
+            self.schemas.push(entry);
+            self.schemas.len() - 1
+        });
+        self.clear_error();
+        index as i64 + 1
+    }
+
+    /// Adds a channel, returning its ID. If a channel with equivalent content was added previously,
+    /// its ID is returned. Returns -1 if `schema_id` was not returned by `add_schema()`.
+    #[func]
+    pub fn add_channel(
+        &mut self,
+        schema_id: i32,
+        topic: GString,
+        message_encoding: GString,
+        metadata: Dictionary,
+    ) -> i64 {
+        if schema_id < 0 || schema_id as usize > self.schemas.len() {
+            self.set_error(format!(
+                "add_channel failed: unknown schema id {}",
+                schema_id
+            ));
+            return -1;
+        }
+        let entry = ChannelEntry {
+            schema_id: schema_id as u16,
+            topic: topic.to_string(),
+            message_encoding: message_encoding.to_string(),
+            metadata: dict_to_btreemap(&metadata),
+        };
+        let existing = self.channels.iter().position(|c| {
+            c.schema_id == entry.schema_id
+                && c.topic == entry.topic
+                && c.message_encoding == entry.message_encoding
+                && c.metadata == entry.metadata
+        });
+        let index = existing.unwrap_or_else(|| {
+            self.channels.push(entry);
+            self.channels.len() - 1
+        });
+        self.clear_error();
+        index as i64 + 1
+    }
+
+    /// Buffer the given message (including its embedded channel and schema).
+    #[func]
+    pub fn write(&mut self, message: Gd<MCAPMessage>) -> bool {
+        match message.bind().to_mcap_owned() {
+            Ok(msg) => {
+                self.push(Buffered::Message(msg));
+                self.clear_error();
+                true
+            }
+            Err(err) => {
+                self.set_error(format!(
+                    "write failed to convert MCAPMessage to mcap::Message: {}",
+                    err
+                ));
+                false
+            }
+        }
+    }
+
+    /// Buffer a message for a channel added via `add_channel()`.
+    #[func]
+    pub fn write_to_known_channel(
+        &mut self,
+        header: Gd<MCAPMessageHeader>,
+        data: PackedByteArray,
+    ) -> bool {
+        let header = match header.bind().to_mcap_owned() {
+            Ok(h) => h,
+            Err(err) => {
+                self.set_error(format!("write_to_known_channel failed to convert MCAPMessageHeader to mcap::MessageHeader: {}", err));
+                return false;
+            }
+        };
+        if header.channel_id == 0 || header.channel_id as usize > self.channels.len() {
+            self.set_error(format!(
+                "write_to_known_channel failed: unknown channel id {}",
+                header.channel_id
+            ));
+            return false;
+        }
+        self.push(Buffered::KnownMessage(header, data.to_vec()));
+        self.clear_error();
+        true
+    }
+
+    /// Buffer an attachment. Attachments are evicted like messages, by their log time.
+    #[func]
+    pub fn attach(&mut self, attachment: Gd<MCAPAttachment>) -> bool {
+        match attachment.bind().to_mcap_owned() {
+            Ok(att) => {
+                self.push(Buffered::Attachment(att));
+                self.clear_error();
+                true
+            }
+            Err(err) => {
+                self.set_error(format!(
+                    "attach failed to convert MCAPAttachment to mcap::Attachment: {}",
+                    err
+                ));
+                false
+            }
+        }
+    }
+
+    /// Store a metadata record. Metadata is never evicted and is included in every dump.
+    #[func]
+    pub fn write_metadata(&mut self, metadata: Gd<MCAPMetadata>) -> bool {
+        self.metadata.push(metadata.bind().to_mcap_owned());
+        self.clear_error();
+        true
+    }
+
+    /// Write the current buffer to `path` as a complete MCAP file. Returns true on success.
+    #[func]
+    pub fn dump(&mut self, path: GString) -> bool {
+        let file = match GFile::open(&path, ModeFlags::WRITE) {
+            Ok(f) => f,
+            Err(err) => {
+                self.set_error(format!("Failed to open {}: {}", path, err));
+                return false;
+            }
+        };
+        match self.dump_into(file) {
+            Ok(mut file) => match file.flush() {
+                Ok(()) => {
+                    self.clear_error();
+                    true
+                }
+                Err(e) => {
+                    self.set_error(format!("dump failed to flush {}: {}", path, e));
+                    false
+                }
+            },
+            Err(e) => {
+                self.set_error(format!("dump failed: {}", e));
+                false
+            }
+        }
+    }
+
+    /// Write the current buffer as a complete MCAP and return its bytes (empty on error).
+    #[func]
+    pub fn dump_to_bytes(&mut self) -> PackedByteArray {
+        match self.dump_into(Cursor::new(Vec::new())) {
+            Ok(cursor) => {
+                self.clear_error();
+                PackedByteArray::from(cursor.into_inner())
+            }
+            Err(e) => {
+                self.set_error(format!("dump_to_bytes failed: {}", e));
+                PackedByteArray::new()
+            }
+        }
+    }
+
+    /// Drop all buffered messages and attachments. Schemas, channels and metadata are kept.
+    #[func]
+    pub fn clear(&mut self) {
+        self.records.clear();
+        self.buffered_bytes = 0;
+        self.newest_log_time = 0;
+    }
+
+    /// Number of messages currently buffered.
+    #[func]
+    pub fn get_buffered_message_count(&self) -> i64 {
+        self.records.iter().filter(|r| r.is_message()).count() as i64
+    }
+
+    /// Payload bytes currently buffered (message and attachment data).
+    #[func]
+    pub fn get_buffered_bytes(&self) -> i64 {
+        self.buffered_bytes as i64
+    }
+
+    /// Log time span (usec) covered by the buffered records, or 0 if empty.
+    #[func]
+    pub fn get_buffered_duration_usec(&self) -> i64 {
+        let oldest = self.records.iter().map(Buffered::log_time).min();
+        match oldest {
+            Some(t) => self.newest_log_time.saturating_sub(t) as i64,
+            None => 0,
+        }
+    }
+
+    /// Total number of messages evicted since creation.
+    #[func]
+    pub fn get_evicted_message_count(&self) -> i64 {
+        self.evicted_messages as i64
+    }
+
+    /// Number of buffered `write()` messages the last dump skipped because their embedded channel
+    /// or schema clashed with another one using the same ID.
+    #[func]
+    pub fn get_skipped_message_count(&self) -> i64 {
+        self.skipped_messages as i64
+    }
+
+    /// Returns the last encountered error message, or empty string if none.
+    #[func]
+    pub fn get_last_error(&self) -> GString {
+        GString::from(self.last_error.as_str())
+    }
+}
@@ -0,0 +1,486 @@
+use crate::time::Clock;
+use crate::types::*;
+use crate::util::dict_to_btreemap;
+use crate::writer::MCAPWriter;
+use godot::classes::{RefCounted, Time};
+use godot::prelude::*;
+use mcap::{Channel, Schema};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+#[derive(GodotClass)]
+/// MCAP writer that splits a recording into multiple segment files.
+///
+/// Overview
+/// - Wraps an `MCAPWriter` and starts a new file whenever the current segment crosses one of the
+///   configured thresholds: byte size, message count, log-time span or wall-clock duration.
+/// - Schemas and channels registered through `add_schema()` / `add_channel()`, or embedded in
+///   messages passed to `write()`, are registered at the start of every segment under the same IDs,
+///   so IDs returned once stay valid for the whole session.
+/// - Each segment is a complete, indexed MCAP file; `segment_finished(path)` fires after a segment
+///   has been finalized successfully.
+///
+/// Segment paths
+/// - `open(path_pattern)` takes a pattern in which `{index}` is replaced by the zero-padded segment
+///   index (starting at 0) and `{timestamp}` by the local date/time at which the segment was opened.
+/// - If the pattern does not contain `{index}`, `_{index}` is inserted before the extension, so
+///   segments opened within the same second never share a path.
+///
+/// Example
+/// ```gdscript
+/// var w := MCAPRotatingWriter.new()
+/// w.max_segment_bytes = 256 * 1024 * 1024
+/// w.max_segment_wall_duration_usec = 10 * 60 * 1_000_000
+/// w.segment_finished.connect(func(path): print("finished ", path))
+/// if not w.open("user://soak_{timestamp}_{index}.mcap"):
+///     push_error(w.get_last_error())
+/// var ch_id := w.add_channel(0, "/state", "json", {})
+/// w.write_to_known_channel(MCAPMessageHeader.create(ch_id), payload)
+/// w.close()
+/// ```
+///
+/// Notes
+/// - Thresholds are checked before each message is written; a value of 0 disables a threshold.
+/// - The byte size only grows when a chunk is finished, so segments may overshoot by up to one chunk.
+/// - From the second segment on, schemas without any channel occupy channel IDs counting down from
+///   65535.
+/// - The timestamp offset is carried over to every segment.
+#[class(init)]
+pub struct MCAPRotatingWriter {
+    base: Base<RefCounted>,
+    /// Options used for every segment. Modify these before calling `open()`.
+    #[export]
+    options: Option<Gd<MCAPWriteOptions>>,
+    /// Start a new segment once the current file reaches this many bytes (0 = unlimited).
+    #[export]
+    max_segment_bytes: i64,
+    /// Start a new segment once the current file holds this many messages (0 = unlimited).
+    #[export]
+    max_segment_messages: i64,
+    /// Start a new segment once a message's log time is this far (usec) past the segment's first message (0 = unlimited).
+    #[export]
+    max_segment_log_duration_usec: i64,
+    /// Start a new segment once the current file has been open this long (usec of real time, 0 = unlimited).
+    #[export]
+    max_segment_wall_duration_usec: i64,
+    path_pattern: GString,
+    writer: Option<Gd<MCAPWriter>>,
+    current_path: GString,
+    segment_index: i64,
+    segment_messages: u64,
+    segment_first_log_time: Option<u64>,
+    segment_opened_at: Option<Instant>,
+    // Every schema and channel of the session by ID, registered again in each new segment
+    schemas: BTreeMap<u16, Arc<Schema<'static>>>,
+    channels: BTreeMap<u16, Arc<Channel<'static>>>,
+    timestamp_offset_usec: i64,
+    last_error: String,
+}
+
+impl MCAPRotatingWriter {
+    fn set_error(&mut self, msg: impl Into<String>) {
+        let s = msg.into();
+        self.last_error = s.clone();
+        godot_error!("{}", s);
+    }
+
+    fn clear_error(&mut self) {
+        self.last_error.clear();
+    }
+
+    // Mirror the inner writer's error state (it already logged the error).
+    fn sync_error_from(&mut self, writer: &Gd<MCAPWriter>) {
+        self.last_error = writer.bind().get_last_error().to_string();
+    }
+
+    fn format_segment_path(&self, index: i64) -> GString {
+        let mut pattern = self.path_pattern.to_string();
+        if !pattern.contains("{index}") {
+            let slash = pattern.rfind('/').map(|i| i + 1).unwrap_or(0);
+            match pattern[slash..].rfind('.') {
+                Some(dot) => pattern.insert_str(slash + dot, "_{index}"),
+                None => pattern.push_str("_{index}"),
+            }
+        }
+        // ISO-like local time without ':' so the name is valid on every platform
+        let timestamp = Time::singleton()
+            .get_datetime_string_from_system()
+            .to_string()
+            .replace(':', "-");
+        let path = pattern
+            .replace("{index}", &format!("{:04}", index))
+            .replace("{timestamp}", &timestamp);
+        GString::from(path.as_str())
+    }
+
+    // Open segment `index`, register the session's IDs and carry the timestamp offset over.
+    fn open_segment(&mut self, index: i64) -> bool {
+        let path = self.format_segment_path(index);
+        let mut writer = MCAPWriter::new_gd();
+        writer.bind_mut().options = self.options.clone();
+        let opened = if self.schemas.is_empty() && self.channels.is_empty() {
+            writer.bind_mut().open(path.clone())
+        } else {
+            let channels: Vec<_> = self.channels.values().cloned().collect();
+            let schemas: Vec<_> = self.schemas.values().cloned().collect();
+            writer
+                .bind_mut()
+                .open_registered(path.clone(), &channels, &schemas)
+        };
+        if !opened {
+            self.sync_error_from(&writer);
+            return false;
+        }
+        if !writer
+            .bind_mut()
+            .set_timestamp_offset_usec(self.timestamp_offset_usec)
+        {
+            self.sync_error_from(&writer);
+            return false;
+        }
+
+        self.writer = Some(writer);
+        self.current_path = path;
+        self.segment_index = index;
+        self.segment_messages = 0;
+        self.segment_first_log_time = None;
+        self.segment_opened_at = Some(Instant::now());
+        true
+    }
+
+    // Finalize the current segment and announce it.
+    fn close_segment(&mut self) -> bool {
+        let Some(mut writer) = self.writer.take() else {
+            return true;
+        };
+        if !writer.bind_mut().close() {
+            self.sync_error_from(&writer);
+            return false;
+        }
+        let path = self.current_path.clone();
+        self.signals().segment_finished().emit(&path);
+        true
+    }
+
+    fn rotation_due(&self, log_time: u64) -> bool {
+        if self.segment_messages == 0 {
+            return false;
+        }
+        if self.max_segment_messages > 0
+            && self.segment_messages >= self.max_segment_messages as u64
+        {
+            return true;
+        }
+        if self.max_segment_bytes > 0
+            && let Some(w) = &self.writer
+            && w.bind().get_bytes_written() >= self.max_segment_bytes
+        {
+            return true;
+        }
+        if self.max_segment_log_duration_usec > 0
+            && let Some(first) = self.segment_first_log_time
+            && log_time.saturating_sub(first) >= self.max_segment_log_duration_usec as u64
+        {
+            return true;
+        }
+        if self.max_segment_wall_duration_usec > 0
+            && let Some(opened) = self.segment_opened_at
+            && opened.elapsed().as_micros() >= self.max_segment_wall_duration_usec as u128
+        {
+            return true;
+        }
+        false
+    }
+
+    // Rotate if needed, then hand out the writer for the next message.
+    fn writer_for_message(&mut self, caller: &str, log_time: u64) -> Option<Gd<MCAPWriter>> {
+        if self.writer.is_none() {
+            self.set_error(format!("{} called before open()", caller));
+            return None;
+        }
+        if self.rotation_due(log_time) && !self.rotate_now() {
+            return None;
+        }
+        self.writer.clone()
+    }
+
+    fn after_message(&mut self, writer: &Gd<MCAPWriter>, ok: bool, log_time: u64) -> bool {
+        if ok {
+            self.segment_messages += 1;
+            if self.segment_first_log_time.is_none() {
+                self.segment_first_log_time = Some(log_time);
+            }
+            self.clear_error();
+        } else {
+            self.sync_error_from(writer);
+        }
+        ok
+    }
+
+    fn current_writer(&mut self, caller: &str) -> Option<Gd<MCAPWriter>> {
+        if self.writer.is_none() {
+            self.set_error(format!("{} called before open()", caller));
+        }
+        self.writer.clone()
+    }
+}
+
+#[godot_api]
+impl MCAPRotatingWriter {
+    /// Emitted after a segment file has been finalized (on rotation and on `close()`).
+    #[signal]
+    pub fn segment_finished(path: GString);
+
+    /// Opens the first segment. See the class description for the pattern placeholders.
+    #[func]
+    pub fn open(&mut self, path_pattern: GString) -> bool {
+        if self.writer.is_some() {
+            self.set_error("open() called but a segment is already open");
+            return false;
+        }
+        self.clear_error();
+        self.path_pattern = path_pattern;
+        self.schemas.clear();
+        self.channels.clear();
+        self.open_segment(0)
+    }
+
+    /// Returns whether a segment is currently open.
+    #[func]
+    pub fn is_open(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    /// Path of the segment currently being written (or the last one after `close()`).
+    #[func]
+    pub fn get_current_path(&self) -> GString {
+        self.current_path.clone()
+    }
+
+    /// Index of the segment currently being written, starting at 0.
+    #[func]
+    pub fn get_segment_index(&self) -> i64 {
+        self.segment_index
+    }
+
+    /// Number of messages written to the current segment.
+    #[func]
+    pub fn get_segment_message_count(&self) -> i64 {
+        self.segment_messages as i64
+    }
+
+    /// Sets the microsecond offset applied to message and attachment timestamps in every segment.
+    /// Same semantics as `MCAPWriter.set_timestamp_offset_usec()`; locks after the first write.
+    #[func]
+    pub fn set_timestamp_offset_usec(&mut self, offset: i64) -> bool {
+        if let Some(mut w) = self.writer.clone()
+            && !w.bind_mut().set_timestamp_offset_usec(offset)
+        {
+            self.sync_error_from(&w);
+            return false;
+        }
+        self.timestamp_offset_usec = offset;
+        self.clear_error();
+        true
+    }
+
+    /// Convenience helper that treats the current time of the writer's clock as the zero point for
+    /// future writes.
+    #[func]
+    pub fn set_timestamp_offset_to_now(&mut self) -> bool {
+        let now = match &self.writer {
+            Some(w) => w.bind().get_clock_time_usec(),
+            None => match &self.options {
+                Some(o) => Clock::from_options(&o.bind()).now_usec() as i64,
+                None => Clock::default().now_usec() as i64,
+            },
+        };
+        self.set_timestamp_offset_usec(now)
+    }
+
+    /// Returns the currently configured timestamp offset in microseconds.
+    #[func]
+    pub fn get_timestamp_offset_usec(&self) -> i64 {
+        self.timestamp_offset_usec
+    }
+
+    /// Adds a schema, returning its ID (stable across segments). Returns -1 on error.
+    #[func]
+    pub fn add_schema(&mut self, name: GString, encoding: GString, data: PackedByteArray) -> i64 {
+        let Some(mut w) = self.current_writer("add_schema") else {
+            return -1;
+        };
+        let id = w
+            .bind_mut()
+            .add_schema(name.clone(), encoding.clone(), data.clone());
+        if id < 0 {
+            self.sync_error_from(&w);
+            return id;
+        }
+        self.schemas.entry(id as u16).or_insert_with(|| {
+            Arc::new(Schema {
+                id: id as u16,
+                name: name.to_string(),
+                encoding: encoding.to_string(),
+                data: Cow::Owned(data.to_vec()),
+            })
+        });
+        self.clear_error();
+        id
+    }
+
+    /// Adds a channel, returning its ID (stable across segments). Returns -1 on error.
+    #[func]
+    pub fn add_channel(
+        &mut self,
+        schema_id: i32,
+        topic: GString,
+        message_encoding: GString,
+        metadata: Dictionary,
+    ) -> i64 {
+        let Some(mut w) = self.current_writer("add_channel") else {
+            return -1;
+        };
+        let id = w.bind_mut().add_channel(
+            schema_id,
+            topic.clone(),
+            message_encoding.clone(),
+            metadata.clone(),
+        );
+        if id < 0 {
+            self.sync_error_from(&w);
+            return id;
+        }
+        if !self.channels.contains_key(&(id as u16)) {
+            let schema = self.schemas.get(&(schema_id as u16)).cloned();
+            let channel = Channel {
+                id: id as u16,
+                topic: topic.to_string(),
+                schema,
+                message_encoding: message_encoding.to_string(),
+                metadata: dict_to_btreemap(&metadata),
+            };
+            self.channels.insert(channel.id, Arc::new(channel));
+        }
+        self.clear_error();
+        id
+    }
+
+    /// Write the given message, rotating first if a threshold has been crossed.
+    #[func]
+    pub fn write(&mut self, message: Gd<MCAPMessage>) -> bool {
+        let log_time = message.bind().log_time.max(0) as u64;
+        let Some(mut w) = self.writer_for_message("write", log_time) else {
+            return false;
+        };
+        let ok = w.bind_mut().write(message.clone());
+        if ok {
+            // Channels and schemas embedded in messages take IDs too
+            let channel = message.bind().channel.bind().to_mcap_owned();
+            if let Some(schema) = &channel.schema {
+                self.schemas
+                    .entry(schema.id)
+                    .or_insert_with(|| schema.clone());
+            }
+            self.channels
+                .entry(channel.id)
+                .or_insert_with(|| Arc::new(channel));
+        }
+        self.after_message(&w, ok, log_time)
+    }
+
+    /// Write a message to an added channel, rotating first if a threshold has been crossed.
+    #[func]
+    pub fn write_to_known_channel(
+        &mut self,
+        header: Gd<MCAPMessageHeader>,
+        data: PackedByteArray,
+    ) -> bool {
+        let log_time = header.bind().log_time.max(0) as u64;
+        let Some(mut w) = self.writer_for_message("write_to_known_channel", log_time) else {
+            return false;
+        };
+        let ok = w.bind_mut().write_to_known_channel(header, data);
+        self.after_message(&w, ok, log_time)
+    }
+
+    /// Write an attachment into the current segment.
+    #[func]
+    pub fn attach(&mut self, attachment: Gd<MCAPAttachment>) -> bool {
+        let Some(mut w) = self.current_writer("attach") else {
+            return false;
+        };
+        let ok = w.bind_mut().attach(attachment);
+        if !ok {
+            self.sync_error_from(&w);
+        }
+        ok
+    }
+
+    /// Write a metadata record into the current segment.
+    #[func]
+    pub fn write_metadata(&mut self, metadata: Gd<MCAPMetadata>) -> bool {
+        let Some(mut w) = self.current_writer("write_metadata") else {
+            return false;
+        };
+        let ok = w.bind_mut().write_metadata(metadata);
+        if !ok {
+            self.sync_error_from(&w);
+        }
+        ok
+    }
+
+    /// Finish the current chunk of the current segment and flush I/O.
+    #[func]
+    pub fn flush(&mut self) -> bool {
+        let Some(mut w) = self.current_writer("flush") else {
+            return false;
+        };
+        let ok = w.bind_mut().flush();
+        if !ok {
+            self.sync_error_from(&w);
+        }
+        ok
+    }
+
+    /// Finalize the current segment and continue in the next one, regardless of thresholds.
+    #[func]
+    pub fn rotate_now(&mut self) -> bool {
+        if self.writer.is_none() {
+            self.set_error("rotate_now called before open()");
+            return false;
+        }
+        let closed = self.close_segment();
+        let next = self.segment_index + 1;
+        self.open_segment(next) && closed
+    }
+
+    /// Finalize the current segment and stop writing. Returns true on success.
+    #[func]
+    pub fn close(&mut self) -> bool {
+        if self.writer.is_none() {
+            self.set_error("close called before open()");
+            return false;
+        }
+        let ok = self.close_segment();
+        self.segment_opened_at = None;
+        ok
+    }
+
+    /// Returns the last encountered error message, or empty string if none.
+    #[func]
+    pub fn get_last_error(&self) -> GString {
+        GString::from(self.last_error.as_str())
+    }
+}
+
+impl Drop for MCAPRotatingWriter {
+    fn drop(&mut self) {
+        // Finalize the open segment; segment_finished can't be emitted while dropping.
+        if let Some(mut w) = self.writer.take() {
+            let _ = w.bind_mut().close();
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use godot::tools::GFile;
+use std::cell::{Cell, RefCell};
+use std::io::{self, Cursor, Seek, SeekFrom, Write};
+use std::rc::Rc;
+
+/// Position at which output starts while a sink discards it. Keeps records written in that mode
+/// (e.g. placeholder chunks) apart from real ones in the writer's chunk indexes.
+pub(crate) const DISCARD_BASE: u64 = 1 << 62;
+
+/// Destination of a `WriterSink`.
+pub(crate) enum SinkTarget {
+    File(GFile),
+    Memory(Cursor<Vec<u8>>),
+}
+
+/// Output stream handed to `mcap::Writer`.
+///
+/// `mcap::Writer` owns its stream, so the file size is published through a shared counter
+/// instead (the highest offset written so far). Output can be discarded through the shared
+/// `discard` flag; positions are virtual (from `DISCARD_BASE`) while it is set. Bytes queued in
+/// `pending` are written as soon as the flag is cleared, before the writer's next output.
+pub(crate) struct WriterSink {
+    target: SinkTarget,
+    pos: u64,
+    len: Rc<Cell<u64>>,
+    discard: Rc<Cell<bool>>,
+    pending: Rc<RefCell<Vec<u8>>>,
+    // Real position to continue at once discarding stops, and the end of the virtual output
+    resume_at: Option<u64>,
+    virtual_end: u64,
+}
+
+impl WriterSink {
+    pub fn new(file: GFile, len: Rc<Cell<u64>>) -> Self {
+        Self::with_target(SinkTarget::File(file), 0, len)
+    }
+
+    pub fn in_memory(len: Rc<Cell<u64>>) -> Self {
+        Self::with_target(SinkTarget::Memory(Cursor::new(Vec::new())), 0, len)
+    }
+
+    /// Continue a file whose position is at `offset`. Output is discarded until the flag is
+    /// cleared, so the header of a new `mcap::Writer` doesn't land in the file.
+    pub fn appending(file: GFile, offset: u64, len: Rc<Cell<u64>>) -> Self {
+        let sink = Self::with_target(SinkTarget::File(file), offset, len);
+        sink.discard.set(true);
+        sink
+    }
+
+    fn with_target(target: SinkTarget, pos: u64, len: Rc<Cell<u64>>) -> Self {
+        len.set(pos);
+        Self {
+            target,
+            pos,
+            len,
+            discard: Rc::new(Cell::new(false)),
+            pending: Rc::default(),
+            resume_at: None,
+            virtual_end: 0,
+        }
+    }
+
+    /// Flag that makes the sink discard its output while set.
+    pub fn discard_flag(&self) -> Rc<Cell<bool>> {
+        self.discard.clone()
+    }
+
+    /// Queue of raw bytes to write where the output continues once discarding stops.
+    pub fn pending_queue(&self) -> Rc<RefCell<Vec<u8>>> {
+        self.pending.clone()
+    }
+
+    /// Shared counter holding the highest offset written so far.
+    pub fn len_counter(&self) -> Rc<Cell<u64>> {
+        self.len.clone()
+    }
+
+    pub fn into_target(self) -> SinkTarget {
+        self.target
+    }
+
+    // Switch between real and virtual positions according to the flag.
+    fn discarding(&mut self) -> io::Result<bool> {
+        if self.discard.get() {
+            if self.resume_at.is_none() {
+                self.resume_at = Some(self.pos);
+                self.pos = DISCARD_BASE;
+                self.virtual_end = DISCARD_BASE;
+            }
+            return Ok(true);
+        }
+        if let Some(pos) = self.resume_at.take() {
+            self.pos = pos;
+        }
+        let pending = std::mem::take(&mut *self.pending.borrow_mut());
+        if !pending.is_empty() {
+            self.write_all(&pending)?;
+        }
+        Ok(false)
+    }
+}
+
+impl Write for WriterSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.discarding()? {
+            self.pos += buf.len() as u64;
+            self.virtual_end = self.virtual_end.max(self.pos);
+            return Ok(buf.len());
+        }
+        let n = match &mut self.target {
+            SinkTarget::File(f) => f.write(buf)?,
+            SinkTarget::Memory(c) => c.write(buf)?,
+        };
+        self.pos += n as u64;
+        if self.pos > self.len.get() {
+            self.len.set(self.pos);
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.discarding()? {
+            return Ok(());
+        }
+        match &mut self.target {
+            SinkTarget::File(f) => f.flush(),
+            SinkTarget::Memory(c) => c.flush(),
+        }
+    }
+}
+
+impl Seek for WriterSink {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        if self.discarding()? {
+            let to = match pos {
+                SeekFrom::Start(p) => Some(p),
+                SeekFrom::End(d) => self.virtual_end.checked_add_signed(d),
+                SeekFrom::Current(d) => self.pos.checked_add_signed(d),
+            };
+            self.pos = to.ok_or_else(|| io::Error::other("invalid seek"))?;
+            return Ok(self.pos);
+        }
+        self.pos = match &mut self.target {
+            SinkTarget::File(f) => f.seek(pos)?,
+            SinkTarget::Memory(c) => c.seek(pos)?,
+        };
+        Ok(self.pos)
+    }
+}
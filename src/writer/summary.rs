@@ -0,0 +1,393 @@
+use crate::types::MCAPWriteOptions;
+use crate::writer::sink::{DISCARD_BASE, WriterSink};
+use binrw::{BinWrite, Endian};
+use mcap::records::{DataEnd, SchemaHeader, SummaryOffset, op};
+use mcap::{Channel, MAGIC, Message, Schema, Summary, WriteOptions, Writer};
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashSet};
+use std::io::{Cursor, Seek, SeekFrom, Write};
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Summary records requested by `MCAPWriteOptions`, applied to a merged summary.
+pub(crate) struct SummaryOptions {
+    statistics: bool,
+    summary_offsets: bool,
+    chunk_indexes: bool,
+    attachment_indexes: bool,
+    metadata_indexes: bool,
+    repeat_channels: bool,
+    repeat_schemas: bool,
+    crc: bool,
+}
+
+impl SummaryOptions {
+    pub fn new(o: &MCAPWriteOptions) -> Self {
+        Self {
+            statistics: o.emit_statistics,
+            summary_offsets: o.emit_summary_offsets,
+            chunk_indexes: o.emit_chunk_indexes,
+            attachment_indexes: o.emit_attachment_indexes,
+            metadata_indexes: o.emit_metadata_indexes,
+            repeat_channels: o.repeat_channels,
+            repeat_schemas: o.repeat_schemas,
+            crc: o.calculate_summary_section_crc,
+        }
+    }
+}
+
+/// Summary section for a data section the `mcap::Writer` only wrote part of.
+///
+/// Used when appending to an existing file (the existing part is described by the summary read
+/// from it) and when recovering a file. It also lets channels and schemas keep IDs the writer
+/// would otherwise hand out again: `reserve_ids` registers them through placeholder messages that
+/// the sink discards, and `finish` leaves those out of the merged summary.
+pub(crate) struct MergedSummary {
+    existing: Summary,
+    options: SummaryOptions,
+    discard: Rc<Cell<bool>>,
+    pending: Rc<RefCell<Vec<u8>>>,
+    len: Rc<Cell<u64>>,
+    // Channels that received one placeholder message, and channels made up to carry a schema
+    reserved_channels: HashSet<u16>,
+    placeholder_channels: HashSet<u16>,
+    // Log-time range of the real messages written through the writer
+    message_bounds: Option<(u64, u64)>,
+}
+
+impl MergedSummary {
+    /// Track the output of `sink`, which the writer is created on afterwards.
+    pub fn new(existing: Summary, options: SummaryOptions, sink: &WriterSink) -> Self {
+        Self {
+            existing,
+            options,
+            discard: sink.discard_flag(),
+            pending: sink.pending_queue(),
+            len: sink.len_counter(),
+            reserved_channels: HashSet::new(),
+            placeholder_channels: HashSet::new(),
+            message_bounds: None,
+        }
+    }
+
+    /// Options for the `mcap::Writer`: its own summary is discarded, but everything needed for the
+    /// merged one has to be collected.
+    pub fn writer_options(opts: WriteOptions) -> WriteOptions {
+        opts.emit_attachment_indexes(true)
+            .emit_metadata_indexes(true)
+            .calculate_data_section_crc(false)
+            .calculate_summary_section_crc(false)
+    }
+
+    /// Record the log time (in file units) of a message written through the writer.
+    pub fn note_message(&mut self, log_time: u64) {
+        self.message_bounds = Some(match self.message_bounds {
+            Some((start, end)) => (start.min(log_time), end.max(log_time)),
+            None => (log_time, log_time),
+        });
+    }
+
+    /// Register `channels` (except the ones in `registered`) and every schema of `schemas` with
+    /// `w` under their own IDs, without writing messages for them.
+    ///
+    /// Unless `in_file` is set (the records are already in the file), the Schema and Channel
+    /// records the writer hasn't written yet are added to the data section as top-level records.
+    /// Schemas no channel refers to are carried by placeholder channels whose IDs count down from
+    /// `u16::MAX`; those IDs stay taken for the rest of the session.
+    pub fn reserve_ids<'a>(
+        &mut self,
+        w: &mut Writer<WriterSink>,
+        channels: &[Arc<Channel<'a>>],
+        schemas: &[Arc<Schema<'a>>],
+        registered: &HashSet<u16>,
+        in_file: bool,
+    ) -> Result<(), String> {
+        let referenced: HashSet<u16> = channels
+            .iter()
+            .filter_map(|c| c.schema.as_ref().map(|s| s.id))
+            .collect();
+        let taken: HashSet<u16> = channels.iter().map(|c| c.id).collect();
+        let mut free_ids = (1..=u16::MAX).rev().filter(|id| !taken.contains(id));
+
+        let mut placeholders = Vec::new();
+        for schema in schemas.iter().filter(|s| !referenced.contains(&s.id)) {
+            let id = free_ids
+                .next()
+                .ok_or("no channel ID left to reserve a schema with")?;
+            placeholders.push(Arc::new(Channel {
+                id,
+                topic: String::new(),
+                schema: Some(schema.clone()),
+                message_encoding: String::new(),
+                metadata: BTreeMap::new(),
+            }));
+        }
+
+        let to_reserve: Vec<_> = channels
+            .iter()
+            .filter(|c| !registered.contains(&c.id))
+            .collect();
+        let mut records = Vec::new();
+        if !in_file {
+            let written: HashSet<u16> = channels
+                .iter()
+                .filter(|c| registered.contains(&c.id))
+                .filter_map(|c| c.schema.as_ref().map(|s| s.id))
+                .collect();
+            for schema in schemas.iter().filter(|s| !written.contains(&s.id)) {
+                push_schema(&mut records, schema)?;
+            }
+            for channel in &to_reserve {
+                push_channel(&mut records, channel)?;
+            }
+        }
+
+        // Finish any real chunk before output is discarded; the records are written once the
+        // sink stops discarding
+        w.flush().map_err(|e| e.to_string())?;
+        self.discard.set(true);
+        self.pending.borrow_mut().extend(records);
+        let mut result = Ok(());
+        for channel in to_reserve.into_iter().chain(&placeholders) {
+            result = w.write(&Message {
+                channel: channel.clone(),
+                sequence: 0,
+                log_time: 0,
+                publish_time: 0,
+                data: Cow::Borrowed(&[]),
+            });
+            if result.is_err() {
+                break;
+            }
+            self.reserved_channels.insert(channel.id);
+        }
+        let result = result.and_then(|_| w.flush());
+        self.discard.set(false);
+        result.map_err(|e| e.to_string())?;
+
+        for channel in &placeholders {
+            self.reserved_channels.remove(&channel.id);
+            self.placeholder_channels.insert(channel.id);
+        }
+        Ok(())
+    }
+
+    /// Finish `w` and write DataEnd, the merged summary, the footer and the end magic in place of
+    /// the writer's own summary. Returns the sink.
+    pub fn finish(mut self, mut w: Writer<WriterSink>) -> Result<WriterSink, String> {
+        w.flush().map_err(|e| e.to_string())?;
+        let data_end = self.len.get();
+        self.discard.set(true);
+        let finished = w.finish();
+        let mut sink = w.into_inner();
+        self.discard.set(false);
+        let summary = self.merge(finished.map_err(|e| e.to_string())?);
+
+        let tail = self.tail(&summary, data_end)?;
+        sink.seek(SeekFrom::Start(data_end))
+            .and_then(|_| sink.write_all(&tail))
+            .and_then(|_| sink.flush())
+            .map_err(|e| e.to_string())?;
+        Ok(sink)
+    }
+
+    /// Drop `w` without writing a summary, leaving the file as it is.
+    pub fn abandon(self, w: Writer<WriterSink>) {
+        self.discard.set(true);
+        drop(w);
+    }
+
+    /// Combine the existing summary with what the writer wrote, leaving out placeholders.
+    fn merge(&mut self, new: Summary) -> Summary {
+        let mut merged = std::mem::take(&mut self.existing);
+        let mut stats = merged.stats.take().unwrap_or_default();
+        let new_stats = new.stats.unwrap_or_default();
+
+        for (id, schema) in new.schemas {
+            merged.schemas.entry(id).or_insert(schema);
+        }
+        for (id, channel) in new.channels {
+            if !self.placeholder_channels.contains(&id) {
+                merged.channels.entry(id).or_insert(channel);
+            }
+        }
+        let chunks = new
+            .chunk_indexes
+            .into_iter()
+            .filter(|c| c.chunk_start_offset < DISCARD_BASE);
+        let chunk_count = merged.chunk_indexes.len();
+        merged.chunk_indexes.extend(chunks);
+        stats.chunk_count += (merged.chunk_indexes.len() - chunk_count) as u32;
+        merged.attachment_indexes.extend(new.attachment_indexes);
+        merged.metadata_indexes.extend(new.metadata_indexes);
+
+        let had_messages = stats.message_count > 0;
+        for (id, count) in new_stats.channel_message_counts {
+            if self.placeholder_channels.contains(&id) {
+                continue;
+            }
+            let count = count - u64::from(self.reserved_channels.contains(&id));
+            if count > 0 {
+                *stats.channel_message_counts.entry(id).or_default() += count;
+                stats.message_count += count;
+            }
+        }
+        if let Some((start, end)) = self.message_bounds {
+            (stats.message_start_time, stats.message_end_time) = if had_messages {
+                (
+                    stats.message_start_time.min(start),
+                    stats.message_end_time.max(end),
+                )
+            } else {
+                (start, end)
+            };
+        }
+        stats.attachment_count += new_stats.attachment_count;
+        stats.metadata_count += new_stats.metadata_count;
+        stats.schema_count = merged.schemas.len() as u16;
+        stats.channel_count = merged.channels.len() as u32;
+        merged.stats = Some(stats);
+        merged
+    }
+
+    /// Serialize everything that follows the data section, which ends at `data_end`.
+    fn tail(&self, summary: &Summary, data_end: u64) -> Result<Vec<u8>, String> {
+        let opts = &self.options;
+        let mut out = Vec::new();
+        push_record(&mut out, op::DATA_END, &DataEnd::default())?;
+        let section_start = out.len();
+        let mut offsets = Vec::new();
+        let mut group = |out: &mut Vec<u8>, opcode: u8, start: usize| {
+            if out.len() > start {
+                offsets.push(SummaryOffset {
+                    group_opcode: opcode,
+                    group_start: data_end + start as u64,
+                    group_length: (out.len() - start) as u64,
+                });
+            }
+        };
+
+        if opts.repeat_schemas {
+            let start = out.len();
+            let schemas: BTreeMap<_, _> = summary.schemas.iter().collect();
+            for schema in schemas.into_values() {
+                push_schema(&mut out, schema)?;
+            }
+            group(&mut out, op::SCHEMA, start);
+        }
+        if opts.repeat_channels {
+            let start = out.len();
+            let channels: BTreeMap<_, _> = summary.channels.iter().collect();
+            for channel in channels.into_values() {
+                push_channel(&mut out, channel)?;
+            }
+            group(&mut out, op::CHANNEL, start);
+        }
+        if opts.statistics
+            && let Some(stats) = &summary.stats
+        {
+            let start = out.len();
+            push_record(&mut out, op::STATISTICS, stats)?;
+            group(&mut out, op::STATISTICS, start);
+        }
+        if opts.chunk_indexes {
+            let start = out.len();
+            for index in &summary.chunk_indexes {
+                push_record(&mut out, op::CHUNK_INDEX, index)?;
+            }
+            group(&mut out, op::CHUNK_INDEX, start);
+        }
+        if opts.attachment_indexes {
+            let start = out.len();
+            for index in &summary.attachment_indexes {
+                push_record(&mut out, op::ATTACHMENT_INDEX, index)?;
+            }
+            group(&mut out, op::ATTACHMENT_INDEX, start);
+        }
+        if opts.metadata_indexes {
+            let start = out.len();
+            for index in &summary.metadata_indexes {
+                push_record(&mut out, op::METADATA_INDEX, index)?;
+            }
+            group(&mut out, op::METADATA_INDEX, start);
+        }
+
+        let summary_end = out.len();
+        let mut summary_offset_start = 0;
+        if opts.summary_offsets {
+            summary_offset_start = data_end + out.len() as u64;
+            for offset in &offsets {
+                push_record(&mut out, op::SUMMARY_OFFSET, offset)?;
+            }
+        }
+        // Like the mcap writer, point at the summary only if it has any records
+        let summary_start = if summary_end > section_start {
+            data_end + section_start as u64
+        } else {
+            0
+        };
+        out.push(op::FOOTER);
+        out.extend_from_slice(&20u64.to_le_bytes());
+        out.extend_from_slice(&summary_start.to_le_bytes());
+        out.extend_from_slice(&summary_offset_start.to_le_bytes());
+        // The CRC covers the summary section and the footer up to the CRC itself
+        let crc = if opts.crc {
+            crc32fast::hash(&out[section_start..])
+        } else {
+            0
+        };
+        out.extend_from_slice(&crc.to_le_bytes());
+        out.extend_from_slice(MAGIC);
+        Ok(out)
+    }
+}
+
+fn encode<T>(value: &T) -> Result<Vec<u8>, String>
+where
+    T: for<'a> BinWrite<Args<'a> = ()>,
+{
+    let mut body = Vec::new();
+    value
+        .write_options(&mut Cursor::new(&mut body), Endian::Little, ())
+        .map_err(|e| e.to_string())?;
+    Ok(body)
+}
+
+fn push_body(out: &mut Vec<u8>, opcode: u8, body: &[u8]) {
+    out.push(opcode);
+    out.extend_from_slice(&(body.len() as u64).to_le_bytes());
+    out.extend_from_slice(body);
+}
+
+fn push_record<T>(out: &mut Vec<u8>, opcode: u8, value: &T) -> Result<(), String>
+where
+    T: for<'a> BinWrite<Args<'a> = ()>,
+{
+    push_body(out, opcode, &encode(value)?);
+    Ok(())
+}
+
+fn push_schema(out: &mut Vec<u8>, schema: &Schema) -> Result<(), String> {
+    let header = SchemaHeader {
+        id: schema.id,
+        name: schema.name.clone(),
+        encoding: schema.encoding.clone(),
+    };
+    let mut body = encode(&header)?;
+    body.extend_from_slice(&(schema.data.len() as u32).to_le_bytes());
+    body.extend_from_slice(&schema.data);
+    push_body(out, op::SCHEMA, &body);
+    Ok(())
+}
+
+fn push_channel(out: &mut Vec<u8>, channel: &Channel) -> Result<(), String> {
+    let record = mcap::records::Channel {
+        id: channel.id,
+        schema_id: channel.schema.as_ref().map_or(0, |s| s.id),
+        topic: channel.topic.clone(),
+        message_encoding: channel.message_encoding.clone(),
+        metadata: channel.metadata.clone(),
+    };
+    push_record(out, op::CHANNEL, &record)
+}